@@ -0,0 +1,69 @@
+#![no_main]
+
+use garnish_lang_simple_data::SimpleGarnishData;
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use garnish_lang_utilities::data::{analyze_data, diff_data, diff_stores};
+use garnish_lang_utilities::generate::build_from_bytes;
+use garnish_lang_utilities::{find_cycle, validate, walk_data, ConcatenationIterator, Field, GarnishVisitor, Schema};
+use libfuzzer_sys::fuzz_target;
+
+const MAX_INPUT_LEN: usize = 4096;
+const WORK_LIMIT: usize = 10_000;
+
+struct NoopVisitor;
+
+impl GarnishVisitor<SimpleGarnishData> for NoopVisitor {}
+
+fn schemas() -> Vec<Schema> {
+    vec![
+        Schema::Any,
+        Schema::list_of(Schema::optional(Schema::Type(GarnishDataType::Number))),
+        Schema::record(vec![
+            Field::required("symbol_1", Schema::list_of(Schema::Any)),
+            Field::optional("symbol_2", Schema::pair(Schema::Any, Schema::Type(GarnishDataType::Symbol))),
+        ]),
+        Schema::one_of(vec![Schema::Type(GarnishDataType::CharList), Schema::list_of(Schema::Type(GarnishDataType::Pair))]),
+    ]
+}
+
+/// Whether every value in the store can be iterated as a concatenation within the work limit.
+/// Utilities that read all the items of a concatenation take work exponential in its depth when it shares its branches.
+fn expansion_is_bounded(data: &SimpleGarnishData) -> bool {
+    data.get_data_iter().all(|addr| {
+        let mut iter = ConcatenationIterator::with_work_limit(addr, data, WORK_LIMIT);
+        iter.by_ref().for_each(drop);
+        !iter.is_limit_reached()
+    })
+}
+
+fuzz_target!(|bytes: &[u8]| {
+    if bytes.len() > MAX_INPUT_LEN {
+        return;
+    }
+
+    let mut data = SimpleGarnishData::new();
+    let _ = build_from_bytes(bytes, &mut data);
+    let schemas = schemas();
+
+    for addr in data.get_data_iter() {
+        let _ = find_cycle(addr, &data);
+        let _ = walk_data(addr, &data, &mut NoopVisitor);
+    }
+
+    let _ = analyze_data(&data, 5);
+
+    if !expansion_is_bounded(&data) {
+        return;
+    }
+
+    for addr in data.get_data_iter() {
+        let _ = diff_data(addr, &data, addr, &data);
+        let _ = diff_data(addr, &data, data.get_data_len() - 1, &data);
+
+        for schema in schemas.iter() {
+            let _ = validate(addr, &data, schema);
+        }
+    }
+
+    let _ = diff_stores(&data, &SimpleGarnishData::new());
+});
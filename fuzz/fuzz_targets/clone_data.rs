@@ -0,0 +1,33 @@
+#![no_main]
+
+use garnish_lang_simple_data::SimpleGarnishData;
+use garnish_lang_traits::GarnishData;
+use garnish_lang_utilities::data::{
+    clone_data, clone_data_checked, clone_value_stack, collect_garbage, fork_data, restore_snapshot, take_snapshot,
+};
+use garnish_lang_utilities::generate::build_from_bytes;
+use libfuzzer_sys::fuzz_target;
+
+const MAX_INPUT_LEN: usize = 4096;
+
+fuzz_target!(|bytes: &[u8]| {
+    if bytes.len() > MAX_INPUT_LEN {
+        return;
+    }
+
+    let mut data = SimpleGarnishData::new();
+    let _ = build_from_bytes(bytes, &mut data);
+
+    for addr in data.get_data_iter() {
+        let _ = clone_data_checked(addr, &data, &mut SimpleGarnishData::new());
+        let _ = clone_data(addr, &data, &mut SimpleGarnishData::new());
+    }
+
+    let _ = clone_value_stack(&data, &mut SimpleGarnishData::new());
+    let _ = collect_garbage(&data, &mut SimpleGarnishData::new());
+    let _ = fork_data(&data, &mut SimpleGarnishData::new());
+
+    if let Ok(snapshot) = take_snapshot(&data) {
+        let _ = restore_snapshot(&snapshot, &mut SimpleGarnishData::new());
+    }
+});
@@ -0,0 +1,65 @@
+#![no_main]
+
+use garnish_lang_simple_data::SimpleGarnishData;
+use garnish_lang_traits::GarnishData;
+use garnish_lang_utilities::generate::build_from_bytes;
+use garnish_lang_utilities::{
+    association_keys, association_values, filter_list, fold_list, get_association, insert_association, list_items, map_list,
+    match_association, match_items, materialize_range, merge_associations, range_len, remove_association, resolve_slice,
+    reverse_list, zip_lists, ConcatenationIterator, MergePolicy,
+};
+use libfuzzer_sys::fuzz_target;
+
+const MAX_INPUT_LEN: usize = 4096;
+const WORK_LIMIT: usize = 10_000;
+
+/// Whether every value in the store can be iterated as a concatenation within the work limit.
+/// Utilities that read all the items of a concatenation take work exponential in its depth when it shares its branches.
+fn expansion_is_bounded(data: &SimpleGarnishData) -> bool {
+    data.get_data_iter().all(|addr| {
+        let mut iter = ConcatenationIterator::with_work_limit(addr, data, WORK_LIMIT);
+        iter.by_ref().for_each(drop);
+        !iter.is_limit_reached()
+    })
+}
+
+fuzz_target!(|bytes: &[u8]| {
+    if bytes.len() > MAX_INPUT_LEN {
+        return;
+    }
+
+    let mut data = SimpleGarnishData::new();
+    let _ = build_from_bytes(bytes, &mut data);
+    if !expansion_is_bounded(&data) {
+        return;
+    }
+    let key = match <SimpleGarnishData as GarnishData>::parse_symbol("symbol_1") {
+        Ok(key) => key,
+        Err(_) => return,
+    };
+
+    let addrs = data.get_data_iter().collect::<Vec<usize>>();
+    let last = addrs.last().cloned().unwrap_or_default();
+    for addr in addrs {
+        let _ = list_items(addr, &data);
+        let _ = match_items(addr, &data);
+        let _ = match_association(addr, "symbol_1", &data);
+        let _ = get_association(addr, key, &data);
+        let _ = map_list(addr, &mut data, |item, _| Ok(item));
+        let _ = filter_list(addr, &mut data, |item, data| Ok(data.get_data_type(item)? == data.get_data_type(addr)?));
+        let _ = fold_list(addr, &mut data, 0, |count, _, _| Ok(count + 1));
+        let _ = reverse_list(addr, &mut data);
+        let _ = zip_lists(addr, last, &mut data);
+
+        let _ = insert_association(addr, key, last, &mut data);
+        let _ = remove_association(addr, key, &mut data);
+        let _ = merge_associations(addr, last, &mut data, MergePolicy::KeepLeft);
+        let _ = merge_associations(addr, last, &mut data, MergePolicy::Error);
+        let _ = association_keys(addr, &mut data);
+        let _ = association_values(addr, &mut data);
+
+        let _ = resolve_slice(addr, &mut data);
+        let _ = range_len(addr, &data);
+        let _ = materialize_range(addr, &mut data);
+    }
+});
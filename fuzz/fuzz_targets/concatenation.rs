@@ -0,0 +1,36 @@
+#![no_main]
+
+use garnish_lang_simple_data::SimpleGarnishData;
+use garnish_lang_traits::GarnishData;
+use garnish_lang_utilities::generate::build_from_bytes;
+use garnish_lang_utilities::{
+    concatenation_metrics, iterate_concatentation_checked, iterate_rev_concatentation_checked, rebalance_concatenation_with_limit,
+    ConcatenationIterator, CyclePolicy, RebalanceStrategy,
+};
+use libfuzzer_sys::fuzz_target;
+
+const MAX_INPUT_LEN: usize = 4096;
+const WORK_LIMIT: usize = 10_000;
+
+fuzz_target!(|bytes: &[u8]| {
+    if bytes.len() > MAX_INPUT_LEN {
+        return;
+    }
+
+    let mut data = SimpleGarnishData::new();
+    let _ = build_from_bytes(bytes, &mut data);
+
+    for addr in data.get_data_iter() {
+        let _ = ConcatenationIterator::with_work_limit(addr, &data, WORK_LIMIT).count();
+        let _ = ConcatenationIterator::with_work_limit(addr, &data, WORK_LIMIT).rev().count();
+        let _ = iterate_concatentation_checked(addr, &data, CyclePolicy::YieldOnce, |_| ());
+        let _ = iterate_rev_concatentation_checked(addr, &data, CyclePolicy::YieldOnce, |_| ());
+        let _ = concatenation_metrics(addr, &data);
+    }
+
+    let addrs = data.get_data_iter().collect::<Vec<usize>>();
+    for addr in addrs {
+        let _ = rebalance_concatenation_with_limit(addr, &mut data, RebalanceStrategy::Balanced, WORK_LIMIT);
+        let _ = rebalance_concatenation_with_limit(addr, &mut data, RebalanceStrategy::Chunked(3), WORK_LIMIT);
+    }
+});
@@ -0,0 +1,30 @@
+#![no_main]
+
+use garnish_lang_simple_data::SimpleGarnishData;
+use garnish_lang_utilities::{decode_byte_list, encode_char_list, read_bytes, read_string, write_bytes, ConversionMode, TextEncoding};
+use libfuzzer_sys::fuzz_target;
+
+const MAX_INPUT_LEN: usize = 4096;
+const ENCODINGS: [TextEncoding; 4] = [TextEncoding::Utf8, TextEncoding::Utf16Le, TextEncoding::Utf16Be, TextEncoding::Latin1];
+
+fuzz_target!(|bytes: &[u8]| {
+    if bytes.len() > MAX_INPUT_LEN {
+        return;
+    }
+
+    let mut data = SimpleGarnishData::new();
+    let addr = match write_bytes(bytes, &mut data) {
+        Ok(addr) => addr,
+        Err(_) => return,
+    };
+    let _ = read_bytes(addr, &data);
+
+    for encoding in ENCODINGS {
+        for mode in [ConversionMode::Strict, ConversionMode::Lossy] {
+            if let Ok(text) = decode_byte_list(addr, &mut data, encoding, mode) {
+                let _ = read_string(text, &data);
+                let _ = encode_char_list(text, &mut data, encoding, mode);
+            }
+        }
+    }
+});
@@ -0,0 +1,58 @@
+use std::cmp::Ordering;
+
+/// Mapping of data addresses in one store to addresses in another, such as the relocations made when copying data between stores.
+/// Other values can be mapped to by setting `Target`.
+///
+/// Entries are kept sorted by source address, since [`garnish_lang_traits::GarnishData::Size`] is only required to be [`PartialOrd`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddressMap<Size, Target = Size> {
+    items: Vec<(Size, Target)>,
+}
+
+impl<Size: PartialOrd, Target> AddressMap<Size, Target> {
+    pub fn new() -> Self {
+        Self { items: vec![] }
+    }
+
+    fn search(&self, from: &Size) -> Result<usize, usize> {
+        self.items.binary_search_by(|(item, _)| item.partial_cmp(from).unwrap_or(Ordering::Less))
+    }
+
+    pub fn get(&self, from: &Size) -> Option<&Target> {
+        self.search(from).ok().map(|index| &self.items[index].1)
+    }
+
+    pub fn contains(&self, from: &Size) -> bool {
+        self.search(from).is_ok()
+    }
+
+    /// Returns the previously mapped address if the source address was already in the map.
+    pub fn insert(&mut self, from: Size, to: Target) -> Option<Target> {
+        match self.search(&from) {
+            Ok(index) => Some(std::mem::replace(&mut self.items[index].1, to)),
+            Err(index) => {
+                self.items.insert(index, (from, to));
+                None
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Iterates entries in order of source address.
+    pub fn iter(&self) -> impl Iterator<Item = (&Size, &Target)> {
+        self.items.iter().map(|(from, to)| (from, to))
+    }
+}
+
+impl<Size: PartialOrd, Target> Default for AddressMap<Size, Target> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,49 @@
+use std::cmp::Ordering;
+
+/// Sorted set of data addresses.
+///
+/// [`garnish_lang_traits::GarnishData::Size`] is only required to be [`PartialOrd`], so addresses are kept in a sorted list instead of a hash set.
+#[derive(Debug, Clone)]
+pub(crate) struct AddressSet<Size> {
+    items: Vec<Size>,
+}
+
+impl<Size: PartialOrd> AddressSet<Size> {
+    pub(crate) fn new() -> Self {
+        Self { items: vec![] }
+    }
+
+    fn search(&self, addr: &Size) -> Result<usize, usize> {
+        self.items.binary_search_by(|item| item.partial_cmp(addr).unwrap_or(Ordering::Less))
+    }
+
+    pub(crate) fn contains(&self, addr: &Size) -> bool {
+        self.search(addr).is_ok()
+    }
+
+    /// Returns false if the address was already in the set.
+    pub(crate) fn insert(&mut self, addr: Size) -> bool {
+        match self.search(&addr) {
+            Ok(_) => false,
+            Err(index) => {
+                self.items.insert(index, addr);
+                true
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, addr: &Size) -> bool {
+        match self.search(addr) {
+            Ok(index) => {
+                self.items.remove(index);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Iterates addresses in ascending order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Size> {
+        self.items.iter()
+    }
+}
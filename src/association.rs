@@ -0,0 +1,359 @@
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::data::add_list;
+use crate::{list_items, UtilityError};
+
+/// How [`merge_associations`] handles a key found in both lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    KeepLeft,
+    KeepRight,
+    /// Stop merging and return [`UtilityError::KeyConflict`].
+    Error,
+}
+
+/// Value of the association with the given key in a List, Concatenation or Slice.
+///
+/// Lists are searched with [`GarnishData::get_list_item_with_symbol`]. Since it only covers items added as associations,
+/// each item is checked in order if it finds nothing or returns an error, as are the items of Concatenations and Slices.
+/// When more than one item has the key, a List found through the implementation's lookup may return any of them,
+/// otherwise the first in order is used.
+pub fn get_association<Data: GarnishData>(
+    addr: Data::Size,
+    key: Data::Symbol,
+    data: &Data,
+) -> Result<Option<Data::Size>, UtilityError<Data::Error>> {
+    if data.get_data_type(addr.clone())? == GarnishDataType::List {
+        if let Ok(Some(value)) = data.get_list_item_with_symbol(addr.clone(), key.clone()) {
+            return Ok(Some(value));
+        }
+    }
+
+    for item in list_items(addr, data)? {
+        if let Some((item_key, _, value)) = association_entry(item, data)? {
+            if item_key == key {
+                return Ok(Some(value));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Creates a new list with the association for `key` set to `value`.
+///
+/// The first item with the key is replaced in place and any others removed. If no item has the key, the association is added to the end.
+pub fn insert_association<Data: GarnishData>(
+    addr: Data::Size,
+    key: Data::Symbol,
+    value: Data::Size,
+    data: &mut Data,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    // read the list first, so nothing is added if it isn't one
+    let existing = list_items(addr, data)?;
+    let key_addr = data.add_symbol(key.clone())?;
+    let pair = data.add_pair((key_addr, value))?;
+
+    let mut items = vec![];
+    let mut inserted = false;
+    for item in existing {
+        match association_entry(item.clone(), data)? {
+            Some((item_key, _, _)) if item_key == key => {
+                if !inserted {
+                    items.push(pair.clone());
+                    inserted = true;
+                }
+            }
+            _ => items.push(item),
+        }
+    }
+
+    if !inserted {
+        items.push(pair);
+    }
+
+    Ok(add_list(items, data)?)
+}
+
+/// Creates a new list without any associations for `key`.
+pub fn remove_association<Data: GarnishData>(
+    addr: Data::Size,
+    key: Data::Symbol,
+    data: &mut Data,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let mut items = vec![];
+    for item in list_items(addr, data)? {
+        match association_entry(item.clone(), data)? {
+            Some((item_key, _, _)) if item_key == key => {}
+            _ => items.push(item),
+        }
+    }
+
+    Ok(add_list(items, data)?)
+}
+
+/// Creates a new list of the items in `left` followed by the items in `right` whose keys aren't in `left`.
+///
+/// Keys found in both lists keep their position from `left`, with the value chosen by `policy`.
+/// Items that aren't associations are kept from both lists.
+pub fn merge_associations<Data: GarnishData>(
+    left: Data::Size,
+    right: Data::Size,
+    data: &mut Data,
+    policy: MergePolicy,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let left_items = list_items(left, data)?;
+    let mut right_items = vec![];
+    for item in list_items(right, data)? {
+        right_items.push((item.clone(), association_entry(item, data)?));
+    }
+
+    let mut items = vec![];
+    for item in left_items {
+        let key = match association_entry(item.clone(), data)? {
+            Some((key, _, _)) => key,
+            None => {
+                items.push(item);
+                continue;
+            }
+        };
+
+        let conflict = right_items.iter().position(|(_, entry)| entry.as_ref().is_some_and(|(right_key, _, _)| *right_key == key));
+        match conflict {
+            None => items.push(item),
+            Some(index) => {
+                let (right_item, _) = right_items.remove(index);
+                match policy {
+                    MergePolicy::KeepLeft => items.push(item),
+                    MergePolicy::KeepRight => items.push(right_item),
+                    MergePolicy::Error => Err(UtilityError::KeyConflict(format!("{} is in both lists", key)))?,
+                }
+
+                right_items.retain(|(_, entry)| !matches!(entry, Some((right_key, _, _)) if *right_key == key));
+            }
+        }
+    }
+
+    items.extend(right_items.into_iter().map(|(item, _)| item));
+
+    Ok(add_list(items, data)?)
+}
+
+/// Creates a new list of the keys of each association, in order.
+pub fn association_keys<Data: GarnishData>(addr: Data::Size, data: &mut Data) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let mut keys = vec![];
+    for item in list_items(addr, data)? {
+        if let Some((_, key, _)) = association_entry(item, data)? {
+            keys.push(key);
+        }
+    }
+
+    Ok(add_list(keys, data)?)
+}
+
+/// Creates a new list of the values of each association, in order.
+pub fn association_values<Data: GarnishData>(addr: Data::Size, data: &mut Data) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let mut values = vec![];
+    for item in list_items(addr, data)? {
+        if let Some((_, _, value)) = association_entry(item, data)? {
+            values.push(value);
+        }
+    }
+
+    Ok(add_list(values, data)?)
+}
+
+/// Key symbol, key address and value address of a symbol keyed pair.
+type AssociationEntry<Data> = (<Data as GarnishData>::Symbol, <Data as GarnishData>::Size, <Data as GarnishData>::Size);
+
+fn association_entry<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Option<AssociationEntry<Data>>, Data::Error> {
+    if data.get_data_type(addr.clone())? != GarnishDataType::Pair {
+        return Ok(None);
+    }
+
+    let (key, value) = data.get_pair(addr)?;
+    match data.get_data_type(key.clone())? {
+        GarnishDataType::Symbol => Ok(Some((data.get_symbol(key.clone())?, key, value))),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{DataError, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::GarnishData;
+    use crate::mock::MockData;
+    use crate::testing::GarnishValue;
+    use crate::{
+        assert_garnish_eq, association_keys, association_values, get_association, insert_association, merge_associations,
+        remove_association, MergePolicy, UtilityError, ValueBuilder,
+    };
+
+    type Value = GarnishValue<SimpleNumber, char, u8, u64, usize>;
+
+    fn number(n: i32) -> Value {
+        GarnishValue::Number(SimpleNumber::Integer(n))
+    }
+
+    fn symbol(name: &str) -> u64 {
+        <SimpleGarnishData as GarnishData>::parse_symbol(name).unwrap()
+    }
+
+    fn assoc(name: &str, n: i32) -> Value {
+        GarnishValue::pair(GarnishValue::Symbol(symbol(name)), number(n))
+    }
+
+    fn make_record<Data: GarnishData>(data: &mut Data, a: i32, b: i32) -> Data::Size
+    where
+        Data::Number: From<i32>,
+    {
+        ValueBuilder::new(data)
+            .list(|l| l.assoc("a", |v| v.number(a.into())).item(|v| v.unit()).assoc("b", |v| v.number(b.into())))
+            .unwrap()
+    }
+
+    #[test]
+    fn lookup() {
+        let mut data = SimpleGarnishData::new();
+        let record = make_record(&mut data, 1, 2);
+        let concat = ValueBuilder::new(&mut data)
+            .concatenation(|v| v.value(record), |v| v.list(|l| l.assoc("c", |v| v.number(3.into()))))
+            .unwrap();
+
+        let value = get_association(record, symbol("b"), &data).unwrap().unwrap();
+        let concat_value = get_association(concat, symbol("c"), &data).unwrap().unwrap();
+
+        assert_garnish_eq!(data, value, number(2));
+        assert_garnish_eq!(data, concat_value, number(3));
+        assert_eq!(get_association(record, symbol("c"), &data).unwrap(), None);
+    }
+
+    #[test]
+    fn lookup_list_by_symbol() {
+        let mut data = MockData::wrap(SimpleGarnishData::new());
+        let record = make_record(&mut data, 1, 2);
+
+        let value = get_association(record, symbol("b"), &data).unwrap().unwrap();
+
+        assert_eq!(data.inner().unwrap().get_number(value).unwrap(), SimpleNumber::Integer(2));
+        assert_eq!(data.call_count("get_list_item_with_symbol"), 1);
+        assert_eq!(data.call_count("get_list_item"), 0);
+    }
+
+    #[test]
+    fn lookup_fallback() {
+        let mut data = MockData::wrap(SimpleGarnishData::new());
+        let record = make_record(&mut data, 1, 2);
+        data.fail("get_list_item_with_symbol", DataError::from("unsupported".to_string()));
+
+        let value = get_association(record, symbol("a"), &data).unwrap().unwrap();
+
+        assert_eq!(data.inner().unwrap().get_number(value).unwrap(), SimpleNumber::Integer(1));
+    }
+
+    fn make_list(data: &mut SimpleGarnishData, entries: &[(&str, i32)], is_associative: bool) -> usize {
+        let mut items = vec![];
+        for (name, n) in entries {
+            let key = data.add_symbol(symbol(name)).unwrap();
+            let value = data.add_number((*n).into()).unwrap();
+            items.push(data.add_pair((key, value)).unwrap());
+        }
+
+        data.start_list(items.len()).unwrap();
+        for item in items {
+            data.add_to_list(item, is_associative).unwrap();
+        }
+        data.end_list().unwrap()
+    }
+
+    #[test]
+    fn lookup_duplicate_key_uses_first_in_order() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data, &[("a", 1), ("b", 2), ("a", 3)], false);
+        let other = make_list(&mut data, &[("a", 4)], true);
+        let concat = data.add_concatenation(list, other).unwrap();
+
+        let value = get_association(list, symbol("a"), &data).unwrap().unwrap();
+        let concat_value = get_association(concat, symbol("a"), &data).unwrap().unwrap();
+
+        assert_garnish_eq!(data, value, number(1));
+        assert_garnish_eq!(data, concat_value, number(1));
+    }
+
+    #[test]
+    fn lookup_duplicate_key_in_associations() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data, &[("a", 1), ("b", 2), ("a", 3)], true);
+
+        let value = get_association(list, symbol("a"), &data).unwrap().unwrap();
+
+        assert!(data.get_number(value).unwrap() == SimpleNumber::Integer(1) || data.get_number(value).unwrap() == SimpleNumber::Integer(3));
+    }
+
+    #[test]
+    fn lookup_not_added_as_association() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data, &[("a", 1), ("b", 2)], false);
+
+        let value = get_association(list, symbol("b"), &data).unwrap().unwrap();
+
+        assert_garnish_eq!(data, value, number(2));
+        assert_eq!(get_association(list, symbol("c"), &data).unwrap(), None);
+    }
+
+    #[test]
+    fn insert_into_non_list_adds_nothing() {
+        let mut data = SimpleGarnishData::new();
+        let not_list = data.add_number(10.into()).unwrap();
+        let len = data.get_data_len();
+
+        let result = insert_association(not_list, symbol("a"), not_list, &mut data);
+
+        assert!(matches!(result, Err(UtilityError::UnsupportedType(_))));
+        assert_eq!(data.get_data_len(), len);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut data = SimpleGarnishData::new();
+        let record = make_record(&mut data, 1, 2);
+        let ten = data.add_number(10.into()).unwrap();
+
+        let replaced = insert_association(record, symbol("a"), ten, &mut data).unwrap();
+        let added = insert_association(record, symbol("c"), ten, &mut data).unwrap();
+        let removed = remove_association(record, symbol("a"), &mut data).unwrap();
+
+        assert_garnish_eq!(data, replaced, GarnishValue::List(vec![assoc("a", 10), GarnishValue::Unit, assoc("b", 2)]));
+        assert_garnish_eq!(data, added, GarnishValue::List(vec![assoc("a", 1), GarnishValue::Unit, assoc("b", 2), assoc("c", 10)]));
+        assert_garnish_eq!(data, removed, GarnishValue::List(vec![GarnishValue::Unit, assoc("b", 2)]));
+        assert_eq!(data.get_list_associations_len(added).unwrap(), 3);
+    }
+
+    #[test]
+    fn merge() {
+        let mut data = SimpleGarnishData::new();
+        let left = make_record(&mut data, 1, 2);
+        let right = ValueBuilder::new(&mut data)
+            .list(|l| l.assoc("c", |v| v.number(3.into())).assoc("a", |v| v.number(4.into())))
+            .unwrap();
+
+        let keep_left = merge_associations(left, right, &mut data, MergePolicy::KeepLeft).unwrap();
+        let keep_right = merge_associations(left, right, &mut data, MergePolicy::KeepRight).unwrap();
+        let error = merge_associations(left, right, &mut data, MergePolicy::Error);
+
+        assert_garnish_eq!(data, keep_left, GarnishValue::List(vec![assoc("a", 1), GarnishValue::Unit, assoc("b", 2), assoc("c", 3)]));
+        assert_garnish_eq!(data, keep_right, GarnishValue::List(vec![assoc("a", 4), GarnishValue::Unit, assoc("b", 2), assoc("c", 3)]));
+        assert!(matches!(error, Err(UtilityError::KeyConflict(_))));
+    }
+
+    #[test]
+    fn keys_and_values() {
+        let mut data = SimpleGarnishData::new();
+        let record = make_record(&mut data, 1, 2);
+
+        let keys = association_keys(record, &mut data).unwrap();
+        let values = association_values(record, &mut data).unwrap();
+
+        assert_garnish_eq!(data, keys, GarnishValue::List(vec![GarnishValue::Symbol(symbol("a")), GarnishValue::Symbol(symbol("b"))]));
+        assert_garnish_eq!(data, values, GarnishValue::List(vec![number(1), number(2)]));
+    }
+}
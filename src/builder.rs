@@ -0,0 +1,264 @@
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::data::add_list;
+
+/// Adds a single value, and anything it's made of, to a [`GarnishData`] implementation.
+///
+/// ```ignore
+/// let addr = ValueBuilder::new(&mut data).list(|l| l.assoc("name", |v| v.chars("x".chars())).item(|v| v.number(1.into())))?;
+/// ```
+pub struct ValueBuilder<'a, Data: GarnishData> {
+    data: &'a mut Data,
+}
+
+impl<'a, Data: GarnishData> ValueBuilder<'a, Data> {
+    pub fn new(data: &'a mut Data) -> Self {
+        ValueBuilder { data }
+    }
+
+    /// Uses a value that has already been added.
+    pub fn value(self, addr: Data::Size) -> Result<Data::Size, Data::Error> {
+        Ok(addr)
+    }
+
+    pub fn unit(self) -> Result<Data::Size, Data::Error> {
+        self.data.add_unit()
+    }
+
+    pub fn boolean(self, value: bool) -> Result<Data::Size, Data::Error> {
+        match value {
+            true => self.data.add_true(),
+            false => self.data.add_false(),
+        }
+    }
+
+    pub fn number(self, value: Data::Number) -> Result<Data::Size, Data::Error> {
+        self.data.add_number(value)
+    }
+
+    pub fn data_type(self, value: GarnishDataType) -> Result<Data::Size, Data::Error> {
+        self.data.add_type(value)
+    }
+
+    pub fn char(self, value: Data::Char) -> Result<Data::Size, Data::Error> {
+        self.data.add_char(value)
+    }
+
+    /// Adds a character list of the given characters as they are, without treating quotes or escapes specially.
+    ///
+    /// For data whose characters aren't [`char`], [`crate::write_str`] converts a Rust string instead.
+    pub fn chars<Chars: IntoIterator<Item = Data::Char>>(self, value: Chars) -> Result<Data::Size, Data::Error> {
+        self.data.start_char_list()?;
+        for c in value {
+            self.data.add_to_char_list(c)?;
+        }
+        self.data.end_char_list()
+    }
+
+    pub fn byte(self, value: Data::Byte) -> Result<Data::Size, Data::Error> {
+        self.data.add_byte(value)
+    }
+
+    pub fn bytes(self, value: &[Data::Byte]) -> Result<Data::Size, Data::Error> {
+        self.data.start_byte_list()?;
+        for byte in value {
+            self.data.add_to_byte_list(byte.clone())?;
+        }
+        self.data.end_byte_list()
+    }
+
+    /// Adds the symbol named `value`, parsing the name with [`GarnishData::parse_add_symbol`].
+    pub fn symbol(self, value: &str) -> Result<Data::Size, Data::Error> {
+        self.data.parse_add_symbol(value)
+    }
+
+    pub fn expression(self, value: Data::Size) -> Result<Data::Size, Data::Error> {
+        self.data.add_expression(value)
+    }
+
+    pub fn external(self, value: Data::Size) -> Result<Data::Size, Data::Error> {
+        self.data.add_external(value)
+    }
+
+    pub fn pair<LeftFn, RightFn>(self, left: LeftFn, right: RightFn) -> Result<Data::Size, Data::Error>
+    where
+        LeftFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+        RightFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+    {
+        let left = left(ValueBuilder::new(self.data))?;
+        let right = right(ValueBuilder::new(self.data))?;
+        self.data.add_pair((left, right))
+    }
+
+    pub fn range<StartFn, EndFn>(self, start: StartFn, end: EndFn) -> Result<Data::Size, Data::Error>
+    where
+        StartFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+        EndFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+    {
+        let start = start(ValueBuilder::new(self.data))?;
+        let end = end(ValueBuilder::new(self.data))?;
+        self.data.add_range(start, end)
+    }
+
+    pub fn concatenation<LeftFn, RightFn>(self, left: LeftFn, right: RightFn) -> Result<Data::Size, Data::Error>
+    where
+        LeftFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+        RightFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+    {
+        let left = left(ValueBuilder::new(self.data))?;
+        let right = right(ValueBuilder::new(self.data))?;
+        self.data.add_concatenation(left, right)
+    }
+
+    pub fn slice<ValueFn, RangeFn>(self, value: ValueFn, range: RangeFn) -> Result<Data::Size, Data::Error>
+    where
+        ValueFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+        RangeFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+    {
+        let value = value(ValueBuilder::new(self.data))?;
+        let range = range(ValueBuilder::new(self.data))?;
+        self.data.add_slice(value, range)
+    }
+
+    /// Adds a list of the items given to the [`ListBuilder`], marking any symbol keyed pairs as associations.
+    pub fn list<ListFn>(self, items: ListFn) -> Result<Data::Size, Data::Error>
+    where
+        ListFn: for<'b> FnOnce(ListBuilder<'b, Data>) -> ListBuilder<'b, Data>,
+    {
+        let builder = items(ListBuilder::new(self.data));
+        match builder.error {
+            Some(e) => Err(e),
+            None => add_list(builder.items, builder.data),
+        }
+    }
+}
+
+/// Collects items for [`ValueBuilder::list`].
+///
+/// After an item fails to build, the rest are skipped and the error is returned from [`ValueBuilder::list`].
+pub struct ListBuilder<'a, Data: GarnishData> {
+    data: &'a mut Data,
+    items: Vec<Data::Size>,
+    error: Option<Data::Error>,
+}
+
+impl<'a, Data: GarnishData> ListBuilder<'a, Data> {
+    fn new(data: &'a mut Data) -> Self {
+        ListBuilder { data, items: vec![], error: None }
+    }
+
+    pub fn item<ItemFn>(mut self, item: ItemFn) -> Self
+    where
+        ItemFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+    {
+        if self.error.is_none() {
+            match item(ValueBuilder::new(self.data)) {
+                Ok(addr) => self.items.push(addr),
+                Err(e) => self.error = Some(e),
+            }
+        }
+
+        self
+    }
+
+    /// Adds a pair of the symbol `key` and the built value.
+    pub fn assoc<ValueFn>(self, key: &str, value: ValueFn) -> Self
+    where
+        ValueFn: FnOnce(ValueBuilder<'_, Data>) -> Result<Data::Size, Data::Error>,
+    {
+        self.item(|v| v.pair(|k| k.symbol(key), value))
+    }
+
+    /// Adds each value in `values` as an item.
+    pub fn items<T, ItemFn>(mut self, values: impl IntoIterator<Item = T>, mut item: ItemFn) -> Self
+    where
+        ItemFn: FnMut(ValueBuilder<'_, Data>, T) -> Result<Data::Size, Data::Error>,
+    {
+        for value in values {
+            self = self.item(|v| item(v, value));
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{DataError, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::GarnishData;
+    use crate::assert_garnish_eq;
+    use crate::mock::MockData;
+    use crate::testing::GarnishValue;
+    use crate::{read_string, ValueBuilder};
+
+    #[test]
+    fn build_list() {
+        let mut data = SimpleGarnishData::new();
+        let sym = <SimpleGarnishData as GarnishData>::parse_symbol("name").unwrap();
+
+        let list = ValueBuilder::new(&mut data)
+            .list(|l| l.assoc("name", |v| v.chars("x".chars())).item(|v| v.number(1.into())).items([2, 3], |v, n| v.number(n.into())))
+            .unwrap();
+
+        assert_garnish_eq!(
+            data,
+            list,
+            GarnishValue::List(vec![
+                GarnishValue::pair(GarnishValue::Symbol(sym), GarnishValue::char_list("x")),
+                GarnishValue::Number(SimpleNumber::Integer(1)),
+                GarnishValue::Number(SimpleNumber::Integer(2)),
+                GarnishValue::Number(SimpleNumber::Integer(3)),
+            ])
+        );
+        assert_eq!(data.get_list_associations_len(list).unwrap(), 1);
+        let (_, value) = data.get_pair(data.get_list_item(list, 0.into()).unwrap()).unwrap();
+        assert_eq!(data.get_list_item_with_symbol(list, sym).unwrap(), Some(value));
+    }
+
+    #[test]
+    fn build_chars_keeps_quotes_and_backslashes() {
+        let mut data = SimpleGarnishData::new();
+
+        let addr = ValueBuilder::new(&mut data).chars("\"a\\b\"\\n".chars()).unwrap();
+
+        assert_eq!(read_string(addr, &data).unwrap(), "\"a\\b\"\\n");
+    }
+
+    #[test]
+    fn build_nested() {
+        let mut data = SimpleGarnishData::new();
+        let existing = data.add_true().unwrap();
+
+        let addr = ValueBuilder::new(&mut data)
+            .concatenation(
+                |v| v.list(|l| l.item(|v| v.list(|l| l.item(|v| v.unit()))).item(|v| v.value(existing))),
+                |v| v.range(|v| v.number(1.into()), |v| v.bytes(&[1, 2])),
+            )
+            .unwrap();
+
+        assert_garnish_eq!(
+            data,
+            addr,
+            GarnishValue::Concatenation(vec![
+                GarnishValue::List(vec![GarnishValue::Unit]),
+                GarnishValue::True,
+                GarnishValue::range(GarnishValue::Number(SimpleNumber::Integer(1)), GarnishValue::ByteList(vec![1, 2])),
+            ])
+        );
+    }
+
+    #[test]
+    fn build_error_skips_remaining_items() {
+        let mut data = MockData::wrap(SimpleGarnishData::new());
+        data.fail_nth("add_number", 1, DataError::from("failed".to_string()));
+
+        let result = ValueBuilder::new(&mut data).list(|l| {
+            l.item(|v| v.number(1.into()))
+                .item(|v| v.number(2.into()))
+                .item(|v| v.number(3.into()))
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "failed");
+        assert_eq!(data.call_count("add_number"), 2);
+        assert_eq!(data.call_count("start_list"), 0);
+    }
+}
@@ -1,4 +1,9 @@
+use std::collections::VecDeque;
 use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::data::add_list;
+use crate::AddressMap;
+use crate::visitor::{traverse, Step, Traversal};
+use crate::{UtilityError, VisitAction};
 
 pub fn iterate_concatentation<
     Data: GarnishData,
@@ -6,33 +11,868 @@ pub fn iterate_concatentation<
 >(
     concat_index: Data::Size,
     data: &Data,
-    mut item_fn: ItemFn
+    item_fn: ItemFn
 ) -> Result<(), Data::Error> {
-    let (current, next) = data.get_concatenation(concat_index)?;
-    let mut stack = vec![];
+    data.get_concatenation(concat_index.clone())?;
+    iterate_internal(concat_index, data, None, false, item_fn).map(|_| ())
+}
+
+pub fn iterate_rev_concatentation<
+    Data: GarnishData,
+    ItemFn: FnMut(Data::Size)
+>(
+    concat_index: Data::Size,
+    data: &Data,
+    item_fn: ItemFn
+) -> Result<(), Data::Error> {
+    data.get_concatenation(concat_index.clone())?;
+    iterate_internal(concat_index, data, None, true, item_fn).map(|_| ())
+}
+
+/// How checked concatenation iteration handles concatenations reached from one of their own children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CyclePolicy {
+    /// Stop iterating and return [`UtilityError::Cycle`].
+    Error,
+    /// Don't walk the concatenation again, continuing with the rest of the items.
+    /// Values shared between branches are still walked each time they're reached.
+    Skip,
+    /// Walk each concatenation and list only the first time it's reached, so values shared between branches are also only yielded once.
+    /// The only policy that bounds work by the number of values in the store.
+    YieldOnce,
+}
+
+/// Same as [`iterate_concatentation`] but safe to use on untrusted data, handling cycles according to the given policy.
+pub fn iterate_concatentation_checked<
+    Data: GarnishData,
+    ItemFn: FnMut(Data::Size)
+>(
+    concat_index: Data::Size,
+    data: &Data,
+    policy: CyclePolicy,
+    item_fn: ItemFn
+) -> Result<(), UtilityError<Data::Error>> {
+    iterate_checked_internal(concat_index, data, policy, false, item_fn)
+}
 
-    stack.push(next);
-    stack.push(current);
+/// Same as [`iterate_rev_concatentation`] but safe to use on untrusted data, handling cycles according to the given policy.
+pub fn iterate_rev_concatentation_checked<
+    Data: GarnishData,
+    ItemFn: FnMut(Data::Size)
+>(
+    concat_index: Data::Size,
+    data: &Data,
+    policy: CyclePolicy,
+    item_fn: ItemFn
+) -> Result<(), UtilityError<Data::Error>> {
+    iterate_checked_internal(concat_index, data, policy, true, item_fn)
+}
+
+fn iterate_checked_internal<
+    Data: GarnishData,
+    ItemFn: FnMut(Data::Size)
+>(
+    concat_index: Data::Size,
+    data: &Data,
+    policy: CyclePolicy,
+    rev: bool,
+    item_fn: ItemFn
+) -> Result<(), UtilityError<Data::Error>> {
+    match iterate_internal(concat_index, data, Some(policy), rev, item_fn)? {
+        Some(addr) => Err(UtilityError::Cycle(format!("concatenation at {} is reachable from its own children", addr))),
+        None => Ok(()),
+    }
+}
+
+/// Yields the items of a concatenation while it's traversed.
+/// Without a policy every value is walked each time it's reached, except concatenations reached from their own children.
+struct IterateTraversal<'a, Data: GarnishData, ItemFn> {
+    data: &'a Data,
+    policy: Option<CyclePolicy>,
+    rev: bool,
+    item_fn: ItemFn,
+    cycle: Option<Data::Size>,
+}
 
-    while let Some(addr) = stack.pop() {
-        match data.get_data_type(addr.clone())? {
+impl<'a, Data: GarnishData, ItemFn: FnMut(Data::Size)> Traversal for IterateTraversal<'a, Data, ItemFn> {
+    type Node = Data::Size;
+    type Context = ();
+    type Leave = ();
+    type Error = Data::Error;
+
+    fn enter(&mut self, addr: Data::Size, _context: ()) -> Result<Step<Data::Size, (), ()>, Data::Error> {
+        match self.data.get_data_type(addr.clone())? {
             GarnishDataType::Concatenation => {
-                let (current, next) = data.get_concatenation(addr.clone())?;
-                stack.push(next);
-                stack.push(current);
+                let (current, next) = self.data.get_concatenation(addr)?;
+                let children = match self.rev {
+                    false => vec![(current, ()), (next, ())],
+                    true => vec![(next, ()), (current, ())],
+                };
+
+                Ok(Step::Children(children, ()))
             }
             GarnishDataType::List => {
-                let list_iter = data.get_list_items_iter(addr.clone());
+                let mut items = self.data.get_list_items_iter(addr.clone()).collect::<Vec<Data::Number>>();
+                if self.rev {
+                    items.reverse();
+                }
+
+                for i in items {
+                    (self.item_fn)(self.data.get_list_item(addr.clone(), i)?);
+                }
 
-                for i in list_iter {
-                    let item = data.get_list_item(addr.clone(), i)?;
+                Ok(Step::Leaf)
+            }
+            _ => {
+                (self.item_fn)(addr);
+                Ok(Step::Leaf)
+            }
+        }
+    }
+
+    fn shared(&mut self, addr: &Data::Size, _context: &()) -> Result<VisitAction, Data::Error> {
+        if self.policy == Some(CyclePolicy::YieldOnce) {
+            match self.data.get_data_type(addr.clone())? {
+                GarnishDataType::Concatenation | GarnishDataType::List => return Ok(VisitAction::SkipChildren),
+                _ => (),
+            }
+        }
+
+        Ok(VisitAction::Continue)
+    }
+
+    fn cycle(&mut self, addr: &Data::Size, _context: &()) -> Result<VisitAction, Data::Error> {
+        match self.policy {
+            Some(CyclePolicy::Error) => {
+                self.cycle = Some(addr.clone());
+                Ok(VisitAction::Stop)
+            }
+            _ => Ok(VisitAction::Continue),
+        }
+    }
+}
+
+/// Iterates the items of a concatenation, returning the concatenation that ended iteration under [`CyclePolicy::Error`].
+fn iterate_internal<
+    Data: GarnishData,
+    ItemFn: FnMut(Data::Size)
+>(
+    concat_index: Data::Size,
+    data: &Data,
+    policy: Option<CyclePolicy>,
+    rev: bool,
+    item_fn: ItemFn
+) -> Result<Option<Data::Size>, Data::Error> {
+    let mut traversal = IterateTraversal { data, policy, rev, item_fn, cycle: None };
+    traverse([(concat_index, ())], &mut traversal)?;
+    Ok(traversal.cycle)
+}
+
+enum ConcatenationSegment<Data: GarnishData> {
+    Value(Data::Size),
+    List(Data::Size, Data::ListIndexIterator),
+}
+
+/// Iterator over the items of a concatenation, flattening nested concatenations and lists.
+///
+/// Items can be taken from either end, so `.rev()` yields the same items as [`iterate_rev_concatentation`].
+pub struct ConcatenationIterator<'a, Data: GarnishData> {
+    data: &'a Data,
+    segments: VecDeque<ConcatenationSegment<Data>>,
+    work_limit: Option<usize>,
+    work: usize,
+    limit_reached: bool,
+}
+
+impl<'a, Data: GarnishData> ConcatenationIterator<'a, Data> {
+    pub fn new(concat_index: Data::Size, data: &'a Data) -> Self {
+        let mut segments = VecDeque::new();
+        segments.push_back(ConcatenationSegment::Value(concat_index));
+
+        Self { data, segments, work_limit: None, work: 0, limit_reached: false }
+    }
 
-                    item_fn(item);
+    /// Iterator that ends after `limit` concatenations, lists and items have been walked, even if items remain.
+    ///
+    /// Concatenations that refer back to themselves, or share values many times over, can otherwise take unbounded work to iterate.
+    /// Use [`ConcatenationIterator::is_limit_reached`] to check if iteration ended early.
+    pub fn with_work_limit(concat_index: Data::Size, data: &'a Data, limit: usize) -> Self {
+        Self { work_limit: Some(limit), ..Self::new(concat_index, data) }
+    }
+
+    pub fn is_limit_reached(&self) -> bool {
+        self.limit_reached
+    }
+
+    fn pop_segment(&mut self, front: bool) -> Option<ConcatenationSegment<Data>> {
+        if self.work_limit.is_some_and(|limit| self.work >= limit) && !self.segments.is_empty() {
+            self.limit_reached = true;
+            self.segments.clear();
+            return None;
+        }
+
+        self.work += 1;
+        match front {
+            true => self.segments.pop_front(),
+            false => self.segments.pop_back(),
+        }
+    }
+
+    fn take_front(&mut self) -> Result<Option<Data::Size>, Data::Error> {
+        while let Some(segment) = self.pop_segment(true) {
+            match segment {
+                ConcatenationSegment::Value(addr) => match self.data.get_data_type(addr.clone())? {
+                    GarnishDataType::Concatenation => {
+                        let (current, next) = self.data.get_concatenation(addr)?;
+                        self.segments.push_front(ConcatenationSegment::Value(next));
+                        self.segments.push_front(ConcatenationSegment::Value(current));
+                    }
+                    GarnishDataType::List => {
+                        let list_iter = self.data.get_list_items_iter(addr.clone());
+                        self.segments.push_front(ConcatenationSegment::List(addr, list_iter));
+                    }
+                    _ => return Ok(Some(addr)),
+                },
+                ConcatenationSegment::List(addr, mut list_iter) => {
+                    if let Some(i) = list_iter.next() {
+                        let item = self.data.get_list_item(addr.clone(), i)?;
+                        self.segments.push_front(ConcatenationSegment::List(addr, list_iter));
+                        return Ok(Some(item));
+                    }
                 }
             }
-            _ => item_fn(addr.clone()),
         }
+
+        Ok(None)
+    }
+
+    fn take_back(&mut self) -> Result<Option<Data::Size>, Data::Error> {
+        while let Some(segment) = self.pop_segment(false) {
+            match segment {
+                ConcatenationSegment::Value(addr) => match self.data.get_data_type(addr.clone())? {
+                    GarnishDataType::Concatenation => {
+                        let (current, next) = self.data.get_concatenation(addr)?;
+                        self.segments.push_back(ConcatenationSegment::Value(current));
+                        self.segments.push_back(ConcatenationSegment::Value(next));
+                    }
+                    GarnishDataType::List => {
+                        let list_iter = self.data.get_list_items_iter(addr.clone());
+                        self.segments.push_back(ConcatenationSegment::List(addr, list_iter));
+                    }
+                    _ => return Ok(Some(addr)),
+                },
+                ConcatenationSegment::List(addr, mut list_iter) => {
+                    if let Some(i) = list_iter.next_back() {
+                        let item = self.data.get_list_item(addr.clone(), i)?;
+                        self.segments.push_back(ConcatenationSegment::List(addr, list_iter));
+                        return Ok(Some(item));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl<'a, Data: GarnishData> Iterator for ConcatenationIterator<'a, Data> {
+    type Item = Result<Data::Size, Data::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.take_front() {
+            Ok(item) => item.map(Ok),
+            Err(e) => {
+                self.segments.clear();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<'a, Data: GarnishData> DoubleEndedIterator for ConcatenationIterator<'a, Data> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self.take_back() {
+            Ok(item) => item.map(Ok),
+            Err(e) => {
+                self.segments.clear();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConcatenationMetrics {
+    /// Number of concatenation nodes on the longest path from the root.
+    pub depth: usize,
+    /// Number of items produced when iterating the concatenation.
+    pub item_count: usize,
+    /// Number of distinct concatenations reachable from the root, each counted once however many times it's shared.
+    pub node_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebalanceStrategy {
+    /// Concatenation tree with each item in its own single item list as leaves, paired from left to right.
+    /// Items are wrapped so lists and concatenations among them aren't flattened when the result is iterated.
+    Balanced,
+    /// Items grouped into lists of the given size, with the lists as leaves of a balanced concatenation tree.
+    Chunked(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebalanceResult<Size> {
+    pub addr: Size,
+    pub before: ConcatenationMetrics,
+    pub after: ConcatenationMetrics,
+}
+
+/// Depth, item count and number of values walked when iterating a value.
+type Measure = (usize, usize, usize);
+
+/// Works out [`ConcatenationMetrics`] of each concatenation once its children have been, keeping the result so shared
+/// concatenations are only walked once.
+struct MetricsTraversal<'a, Data: GarnishData> {
+    data: &'a Data,
+    /// Depth, item count and number of values walked when iterating, for each concatenation left so far.
+    measured: AddressMap<Data::Size, Measure>,
+    cycle: Option<Data::Size>,
+}
+
+impl<'a, Data: GarnishData> MetricsTraversal<'a, Data> {
+    /// Measure of a value that's already been walked.
+    fn measure(&self, addr: Data::Size) -> Result<Measure, Data::Error> {
+        if let Some(measured) = self.measured.get(&addr) {
+            return Ok(*measured);
+        }
+
+        Ok(match self.data.get_data_type(addr.clone())? {
+            GarnishDataType::List => (0, self.data.get_list_items_iter(addr).count(), 1),
+            // only reached when a concatenation refers back to itself, which ends the traversal
+            GarnishDataType::Concatenation => (0, 0, 1),
+            _ => (0, 1, 1),
+        })
+    }
+}
+
+impl<'a, Data: GarnishData> Traversal for MetricsTraversal<'a, Data> {
+    type Node = Data::Size;
+    type Context = ();
+    type Leave = (Data::Size, Data::Size);
+    type Error = Data::Error;
+
+    fn enter(&mut self, addr: Data::Size, _context: ()) -> Result<Step<Data::Size, (), (Data::Size, Data::Size)>, Data::Error> {
+        match self.data.get_data_type(addr.clone())? {
+            GarnishDataType::Concatenation => {
+                let (current, next) = self.data.get_concatenation(addr)?;
+                Ok(Step::Children(vec![(current.clone(), ()), (next.clone(), ())], (current, next)))
+            }
+            _ => Ok(Step::Leaf),
+        }
+    }
+
+    fn leave(&mut self, addr: Data::Size, (current, next): (Data::Size, Data::Size)) -> Result<VisitAction, Data::Error> {
+        let (current_depth, current_items, current_walked) = self.measure(current)?;
+        let (next_depth, next_items, next_walked) = self.measure(next)?;
+        self.measured.insert(addr, (
+            current_depth.max(next_depth) + 1,
+            current_items.saturating_add(next_items),
+            current_walked.saturating_add(next_walked).saturating_add(1),
+        ));
+
+        Ok(VisitAction::Continue)
+    }
+
+    fn cycle(&mut self, addr: &Data::Size, _context: &()) -> Result<VisitAction, Data::Error> {
+        self.cycle = Some(addr.clone());
+        Ok(VisitAction::Stop)
+    }
+}
+
+/// Metrics of the concatenation at the given address, returning [`UtilityError::Cycle`] if a concatenation is reachable from its own children.
+///
+/// Each concatenation is only walked once, so shared concatenations don't multiply the work even though their items are counted each time.
+pub fn concatenation_metrics<Data: GarnishData>(
+    addr: Data::Size,
+    data: &Data,
+) -> Result<ConcatenationMetrics, UtilityError<Data::Error>> {
+    Ok(measure_concatenation(addr, data)?.0)
+}
+
+/// Metrics along with the number of concatenations, lists and other values walked when iterating the concatenation.
+fn measure_concatenation<Data: GarnishData>(
+    addr: Data::Size,
+    data: &Data,
+) -> Result<(ConcatenationMetrics, usize), UtilityError<Data::Error>> {
+    let mut traversal = MetricsTraversal { data, measured: AddressMap::new(), cycle: None };
+    traverse([(addr.clone(), ())], &mut traversal)?;
+
+    if let Some(addr) = traversal.cycle {
+        return Err(UtilityError::Cycle(format!("concatenation at {} is reachable from its own children", addr)));
+    }
+
+    let (depth, item_count, walked) = traversal.measure(addr)?;
+    Ok((ConcatenationMetrics { depth, item_count, node_count: traversal.measured.len() }, walked))
+}
+
+/// Rebuilds the concatenation at the given address as a new value in the same store, keeping item order.
+///
+/// The original concatenation is left untouched. Results with fewer than two leaves are returned as a single list.
+/// Returns [`UtilityError::Cycle`] if a concatenation is reachable from its own children.
+///
+/// Work and the size of the new value grow with the item count, which a concatenation sharing its branches can make
+/// exponential in the number of values in the store. Use [`rebalance_concatenation_with_limit`] on untrusted data.
+pub fn rebalance_concatenation<Data: GarnishData>(
+    addr: Data::Size,
+    data: &mut Data,
+    strategy: RebalanceStrategy,
+) -> Result<RebalanceResult<Data::Size>, UtilityError<Data::Error>> {
+    rebalance_concatenation_with_limit(addr, data, strategy, usize::MAX)
+}
+
+/// Same as [`rebalance_concatenation`] but returns [`UtilityError::LimitExceeded`], without adding anything to the store,
+/// if iterating the concatenation would walk more than `limit` concatenations, lists and items.
+pub fn rebalance_concatenation_with_limit<Data: GarnishData>(
+    addr: Data::Size,
+    data: &mut Data,
+    strategy: RebalanceStrategy,
+    limit: usize,
+) -> Result<RebalanceResult<Data::Size>, UtilityError<Data::Error>> {
+    let (before, walked) = measure_concatenation(addr.clone(), data)?;
+    let work = walked.max(before.item_count);
+    if work > limit {
+        return Err(UtilityError::LimitExceeded(format!(
+            "concatenation at {} takes {} steps to iterate, limit is {}",
+            addr, work, limit
+        )));
+    }
+
+    let mut items = vec![];
+    iterate_concatentation_checked(addr, data, CyclePolicy::Error, |item| items.push(item))?;
+
+    let leaves = match strategy {
+        RebalanceStrategy::Balanced => {
+            let mut lists = vec![];
+            for item in items {
+                lists.push(add_list(vec![item], data)?);
+            }
+
+            lists
+        }
+        RebalanceStrategy::Chunked(size) => {
+            let mut lists = vec![];
+            for chunk in items.chunks(size.max(1)) {
+                lists.push(add_list(chunk.to_vec(), data)?);
+            }
+
+            if lists.is_empty() {
+                lists.push(add_list(vec![], data)?);
+            }
+
+            lists
+        }
+    };
+
+    let mut level = leaves;
+    let node_count = level.len().saturating_sub(1);
+    let mut depth = 0;
+    while level.len() > 1 {
+        depth += 1;
+        let mut next_level = Vec::with_capacity(level.len().div_ceil(2));
+        let mut iter = level.into_iter();
+
+        while let Some(current) = iter.next() {
+            match iter.next() {
+                Some(next) => next_level.push(data.add_concatenation(current, next)?),
+                None => next_level.push(current),
+            }
+        }
+
+        level = next_level;
     }
 
-    Ok(())
+    let new_addr = match level.pop() {
+        Some(addr) => addr,
+        None => add_list(vec![], data)?,
+    };
+
+    let after = ConcatenationMetrics { depth, item_count: before.item_count, node_count };
+
+    Ok(RebalanceResult {
+        addr: new_addr,
+        before,
+        after,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{DataError, SimpleData, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::GarnishData;
+    use crate::{concatenation_metrics, iterate_concatentation, iterate_concatentation_checked, iterate_rev_concatentation, iterate_rev_concatentation_checked, rebalance_concatenation, rebalance_concatenation_with_limit, ConcatenationIterator, ConcatenationMetrics, CyclePolicy, RebalanceStrategy, UtilityError};
+
+    fn make_nested(data: &mut SimpleGarnishData) -> (usize, Vec<usize>) {
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d3 = data.add_number(SimpleNumber::Integer(30)).unwrap();
+        let d4 = data.add_number(SimpleNumber::Integer(40)).unwrap();
+        let d5 = data.add_number(SimpleNumber::Integer(50)).unwrap();
+
+        data.start_list(2).unwrap();
+        data.add_to_list(d2, false).unwrap();
+        data.add_to_list(d3, false).unwrap();
+        let list = data.end_list().unwrap();
+
+        let cat1 = data.add_concatenation(d1, list).unwrap();
+        let cat2 = data.add_concatenation(d4, d5).unwrap();
+        let cat3 = data.add_concatenation(cat1, cat2).unwrap();
+
+        (cat3, vec![d1, d2, d3, d4, d5])
+    }
+
+    #[test]
+    fn iterate() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, expected) = make_nested(&mut data);
+
+        let mut items = vec![];
+        iterate_concatentation(addr, &data, |item| items.push(item)).unwrap();
+
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn iterate_rev() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, mut expected) = make_nested(&mut data);
+        expected.reverse();
+
+        let mut items = vec![];
+        iterate_rev_concatentation(addr, &data, |item| items.push(item)).unwrap();
+
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn iterator() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, expected) = make_nested(&mut data);
+
+        let items = ConcatenationIterator::new(addr, &data).collect::<Result<Vec<usize>, _>>().unwrap();
+
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn iterator_rev() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, mut expected) = make_nested(&mut data);
+        expected.reverse();
+
+        let items = ConcatenationIterator::new(addr, &data).rev().collect::<Result<Vec<usize>, _>>().unwrap();
+
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn iterator_both_ends() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, expected) = make_nested(&mut data);
+
+        let mut iter = ConcatenationIterator::new(addr, &data);
+
+        assert_eq!(iter.next().unwrap().unwrap(), expected[0]);
+        assert_eq!(iter.next_back().unwrap().unwrap(), expected[4]);
+        assert_eq!(iter.next().unwrap().unwrap(), expected[1]);
+        assert_eq!(iter.next_back().unwrap().unwrap(), expected[3]);
+        assert_eq!(iter.next_back().unwrap().unwrap(), expected[2]);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+    }
+
+    #[test]
+    fn iterator_error_ends_iteration() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let addr = data.add_concatenation(d1, 100).unwrap();
+
+        let mut iter = ConcatenationIterator::new(addr, &data);
+
+        assert_eq!(iter.next().unwrap().unwrap(), d1);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn iterator_work_limit() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let addr = data.get_data_len();
+        data.get_data_mut().push(SimpleData::Concatenation(addr, d1));
+
+        let mut iter = ConcatenationIterator::with_work_limit(addr, &data, 100);
+
+        assert!(iter.next().is_none());
+        assert!(iter.is_limit_reached());
+    }
+
+    #[test]
+    fn iterator_work_limit_not_reached() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, expected) = make_nested(&mut data);
+
+        let mut iter = ConcatenationIterator::with_work_limit(addr, &data, 100);
+        let items = iter.by_ref().collect::<Result<Vec<usize>, DataError>>().unwrap();
+
+        assert_eq!(items, expected);
+        assert!(!iter.is_limit_reached());
+    }
+
+    // concatenation of a list and a concatenation of itself and a number
+    fn make_cycle(data: &mut SimpleGarnishData) -> (usize, usize, usize) {
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        data.start_list(1).unwrap();
+        data.add_to_list(d1, false).unwrap();
+        let list = data.end_list().unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let outer = data.get_data_len();
+        data.get_data_mut().push(SimpleData::Concatenation(list, outer + 1));
+        data.get_data_mut().push(SimpleData::Concatenation(outer, d2));
+
+        (outer, d1, d2)
+    }
+
+    #[test]
+    fn checked_without_cycle() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, expected) = make_nested(&mut data);
+
+        let mut items = vec![];
+        iterate_concatentation_checked(addr, &data, CyclePolicy::Error, |item| items.push(item)).unwrap();
+        assert_eq!(items, expected);
+
+        let mut items = vec![];
+        iterate_rev_concatentation_checked(addr, &data, CyclePolicy::Error, |item| items.push(item)).unwrap();
+        items.reverse();
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn checked_cycle_error() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, d1, _) = make_cycle(&mut data);
+
+        let mut items = vec![];
+        let result = iterate_concatentation_checked(addr, &data, CyclePolicy::Error, |item| items.push(item));
+
+        assert!(matches!(result, Err(UtilityError::Cycle(_))));
+        assert_eq!(items, vec![d1]);
+    }
+
+    #[test]
+    fn checked_cycle_skip() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, d1, d2) = make_cycle(&mut data);
+
+        let mut items = vec![];
+        iterate_concatentation_checked(addr, &data, CyclePolicy::Skip, |item| items.push(item)).unwrap();
+        assert_eq!(items, vec![d1, d2]);
+
+        let mut items = vec![];
+        iterate_rev_concatentation_checked(addr, &data, CyclePolicy::Skip, |item| items.push(item)).unwrap();
+        assert_eq!(items, vec![d2, d1]);
+    }
+
+    #[test]
+    fn checked_shared_yield_once() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let shared = data.add_concatenation(d1, d2).unwrap();
+        let addr = data.add_concatenation(shared, shared).unwrap();
+
+        let mut items = vec![];
+        iterate_concatentation_checked(addr, &data, CyclePolicy::Skip, |item| items.push(item)).unwrap();
+        assert_eq!(items, vec![d1, d2, d1, d2]);
+
+        let mut items = vec![];
+        iterate_concatentation_checked(addr, &data, CyclePolicy::YieldOnce, |item| items.push(item)).unwrap();
+        assert_eq!(items, vec![d1, d2]);
+    }
+
+    fn make_left_chain(data: &mut SimpleGarnishData, count: i32) -> (usize, Vec<usize>) {
+        let mut items = vec![];
+        for i in 0..count {
+            items.push(data.add_number(SimpleNumber::Integer(i * 10)).unwrap());
+        }
+
+        let mut addr = data.add_concatenation(items[0], items[1]).unwrap();
+        for item in items.iter().skip(2) {
+            addr = data.add_concatenation(addr, *item).unwrap();
+        }
+
+        (addr, items)
+    }
+
+    #[test]
+    fn metrics() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, _) = make_nested(&mut data);
+
+        assert_eq!(
+            concatenation_metrics(addr, &data).unwrap(),
+            ConcatenationMetrics { depth: 2, item_count: 5, node_count: 3 }
+        );
+    }
+
+    #[test]
+    fn metrics_left_chain() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, _) = make_left_chain(&mut data, 8);
+
+        assert_eq!(
+            concatenation_metrics(addr, &data).unwrap(),
+            ConcatenationMetrics { depth: 7, item_count: 8, node_count: 7 }
+        );
+    }
+
+    #[test]
+    fn rebalance_balanced() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, expected) = make_left_chain(&mut data, 8);
+
+        let result = rebalance_concatenation(addr, &mut data, RebalanceStrategy::Balanced).unwrap();
+
+        assert_eq!(result.before, ConcatenationMetrics { depth: 7, item_count: 8, node_count: 7 });
+        assert_eq!(result.after, ConcatenationMetrics { depth: 3, item_count: 8, node_count: 7 });
+
+        let items = ConcatenationIterator::new(result.addr, &data).collect::<Result<Vec<usize>, _>>().unwrap();
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn rebalance_balanced_odd_count() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, expected) = make_left_chain(&mut data, 5);
+
+        let result = rebalance_concatenation(addr, &mut data, RebalanceStrategy::Balanced).unwrap();
+
+        assert_eq!(result.after, ConcatenationMetrics { depth: 3, item_count: 5, node_count: 4 });
+
+        let items = ConcatenationIterator::new(result.addr, &data).collect::<Result<Vec<usize>, _>>().unwrap();
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn rebalance_balanced_keeps_nested_lists() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d3 = data.add_number(SimpleNumber::Integer(30)).unwrap();
+        data.start_list(2).unwrap();
+        data.add_to_list(d2, false).unwrap();
+        data.add_to_list(d3, false).unwrap();
+        let inner = data.end_list().unwrap();
+        data.start_list(2).unwrap();
+        data.add_to_list(d1, false).unwrap();
+        data.add_to_list(inner, false).unwrap();
+        let outer = data.end_list().unwrap();
+        let d4 = data.add_number(SimpleNumber::Integer(40)).unwrap();
+        let addr = data.add_concatenation(outer, d4).unwrap();
+
+        let result = rebalance_concatenation(addr, &mut data, RebalanceStrategy::Balanced).unwrap();
+
+        assert_eq!(result.after.item_count, 3);
+        let items = ConcatenationIterator::new(result.addr, &data).collect::<Result<Vec<usize>, _>>().unwrap();
+        assert_eq!(items, vec![d1, inner, d4]);
+    }
+
+    #[test]
+    fn metrics_and_rebalance_cycle() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, _, _) = make_cycle(&mut data);
+
+        assert!(matches!(concatenation_metrics(addr, &data), Err(UtilityError::Cycle(_))));
+        assert!(matches!(rebalance_concatenation(addr, &mut data, RebalanceStrategy::Balanced), Err(UtilityError::Cycle(_))));
+    }
+
+    #[test]
+    fn metrics_shared_is_not_cycle() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let shared = data.add_concatenation(d1, d2).unwrap();
+        let addr = data.add_concatenation(shared, shared).unwrap();
+
+        assert_eq!(
+            concatenation_metrics(addr, &data).unwrap(),
+            ConcatenationMetrics { depth: 2, item_count: 4, node_count: 2 }
+        );
+    }
+
+    fn make_doubling_chain(data: &mut SimpleGarnishData, levels: usize) -> usize {
+        let mut addr = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        for _ in 0..levels {
+            addr = data.add_concatenation(addr, addr).unwrap();
+        }
+
+        addr
+    }
+
+    #[test]
+    fn metrics_doubling_chain() {
+        let mut data = SimpleGarnishData::new();
+        let addr = make_doubling_chain(&mut data, 30);
+
+        assert_eq!(
+            concatenation_metrics(addr, &data).unwrap(),
+            ConcatenationMetrics { depth: 30, item_count: 1 << 30, node_count: 30 }
+        );
+    }
+
+    #[test]
+    fn rebalance_over_limit() {
+        let mut data = SimpleGarnishData::new();
+        let addr = make_doubling_chain(&mut data, 30);
+        let len = data.get_data_len();
+
+        let result = rebalance_concatenation_with_limit(addr, &mut data, RebalanceStrategy::Balanced, 1000);
+
+        assert!(matches!(result, Err(UtilityError::LimitExceeded(_))));
+        assert_eq!(data.get_data_len(), len);
+    }
+
+    #[test]
+    fn rebalance_within_limit() {
+        let mut data = SimpleGarnishData::new();
+        let addr = make_doubling_chain(&mut data, 3);
+
+        let result = rebalance_concatenation_with_limit(addr, &mut data, RebalanceStrategy::Chunked(8), 1000).unwrap();
+
+        assert_eq!(result.after, ConcatenationMetrics { depth: 0, item_count: 8, node_count: 0 });
+    }
+
+    #[test]
+    fn rebalance_chunked() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, expected) = make_left_chain(&mut data, 8);
+
+        let result = rebalance_concatenation(addr, &mut data, RebalanceStrategy::Chunked(3)).unwrap();
+
+        assert_eq!(result.after, ConcatenationMetrics { depth: 2, item_count: 8, node_count: 2 });
+
+        let items = ConcatenationIterator::new(result.addr, &data).collect::<Result<Vec<usize>, _>>().unwrap();
+        assert_eq!(items, expected);
+    }
+
+    #[test]
+    fn rebalance_chunked_single_chunk() {
+        let mut data = SimpleGarnishData::new();
+        let (addr, expected) = make_left_chain(&mut data, 4);
+
+        let result = rebalance_concatenation(addr, &mut data, RebalanceStrategy::Chunked(10)).unwrap();
+
+        assert_eq!(result.after, ConcatenationMetrics { depth: 0, item_count: 4, node_count: 0 });
+        assert_eq!(data.get_data().get(result.addr).unwrap().as_list().unwrap(), (expected, vec![]));
+    }
 }
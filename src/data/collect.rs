@@ -0,0 +1,433 @@
+use garnish_lang_traits::{GarnishData, Instruction, TypeConstants};
+use crate::address_set::AddressSet;
+use crate::data::copy::clone_each_internal;
+use crate::data::CloneHandler;
+use crate::{walk_data, AddressMap, GarnishVisitor, UtilityError, VisitAction};
+
+struct MarkVisitor<'a, Size> {
+    marked: &'a mut AddressSet<Size>,
+    /// Number of addresses this visitor added to `marked`.
+    count: usize,
+    cycle: Option<Size>,
+}
+
+impl<'a, Size: PartialOrd> MarkVisitor<'a, Size> {
+    fn mark(&mut self, addr: Size) -> VisitAction {
+        match self.marked.insert(addr) {
+            true => {
+                self.count += 1;
+                VisitAction::Continue
+            }
+            false => VisitAction::SkipChildren,
+        }
+    }
+}
+
+macro_rules! mark_methods {
+    ($($name:ident),*) => {
+        $(
+            fn $name(&mut self, addr: Data::Size, _data: &Data) -> Result<VisitAction, Data::Error> {
+                Ok(self.mark(addr))
+            }
+        )*
+    };
+}
+
+impl<'a, Data: GarnishData> GarnishVisitor<Data> for MarkVisitor<'a, Data::Size> {
+    mark_methods!(
+        visit_invalid,
+        visit_custom,
+        visit_unit,
+        visit_true,
+        visit_false,
+        visit_number,
+        visit_type,
+        visit_char,
+        visit_char_list,
+        visit_byte,
+        visit_byte_list,
+        visit_symbol,
+        visit_expression,
+        visit_external,
+        enter_pair,
+        enter_range,
+        enter_list,
+        enter_concatenation,
+        enter_slice
+    );
+
+    fn visit_cycle(&mut self, addr: Data::Size, _data: &Data) -> Result<VisitAction, Data::Error> {
+        self.cycle.get_or_insert(addr);
+        Ok(VisitAction::Continue)
+    }
+}
+
+/// Entry of a runtime's jump path, read with [`read_jump_path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpPathEntry<Size> {
+    /// Instruction address the runtime returns to when the entry is popped.
+    pub point: Size,
+    /// Number of registers pushed before the entry.
+    pub register_len: Size,
+    /// Whether the entry is kept in the register at `register_len`, as [`GarnishData`] implementations storing stack frames
+    /// as registers do, instead of holding data.
+    pub in_register: bool,
+}
+
+/// Jump path of a runtime, bottom first.
+///
+/// [`GarnishData`] only gives access to the jump path by popping it, so entries are popped from a clone of the runtime.
+pub fn read_jump_path<Data: GarnishData + Clone>(data: &Data) -> Vec<JumpPathEntry<Data::Size>> {
+    let mut scratch = data.clone();
+    let mut entries = vec![];
+
+    loop {
+        let before = scratch.get_register_len();
+        let point = match scratch.pop_jump_path() {
+            Some(point) => point,
+            None => break,
+        };
+
+        let register_len = scratch.get_register_len();
+        entries.push(JumpPathEntry { in_register: register_len < before, point, register_len });
+    }
+
+    entries.reverse();
+    entries
+}
+
+/// Pushes registers and jump path entries read from another runtime in their original order, updating register addresses with `relocate`.
+///
+/// Registers holding a jump path entry are recreated by pushing the entry instead of being pushed as data.
+pub(crate) fn push_registers_and_jump_path<Data: GarnishData, Relocate: Fn(Data::Size) -> Data::Size>(
+    registers: &[Data::Size],
+    jump_path: &[JumpPathEntry<Data::Size>],
+    to: &mut Data,
+    relocate: Relocate,
+) -> Result<(), Data::Error> {
+    let mut entries = jump_path.iter().peekable();
+    let mut index = Data::Size::zero();
+
+    for addr in registers.iter() {
+        let mut replaced = false;
+        while let Some(entry) = entries.next_if(|entry| entry.register_len <= index) {
+            replaced |= entry.in_register && entry.register_len == index;
+            to.push_jump_path(entry.point.clone())?;
+        }
+
+        if !replaced {
+            to.push_register(relocate(addr.clone()))?;
+        }
+
+        index += Data::Size::one();
+    }
+
+    for entry in entries {
+        to.push_jump_path(entry.point.clone())?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn registers<Data: GarnishData>(data: &Data) -> Vec<Data::Size> {
+    data.get_register_iter().filter_map(|i| data.get_register(i)).collect()
+}
+
+/// Addresses a runtime refers to directly. Values on the value stack, registers and data operands of instructions, in that order.
+///
+/// Registers holding a jump path entry (see [`read_jump_path`]) are left out, since the entry is rebuilt by pushing it
+/// to the jump path rather than by copying data.
+pub fn root_addresses<Data: GarnishData + Clone>(data: &Data) -> Vec<Data::Size> {
+    root_addresses_internal(data, &read_jump_path(data))
+}
+
+fn root_addresses_internal<Data: GarnishData>(data: &Data, jump_path: &[JumpPathEntry<Data::Size>]) -> Vec<Data::Size> {
+    let mut roots = vec![];
+
+    for i in data.get_value_iter() {
+        if let Some(addr) = data.get_value(i) {
+            roots.push(addr);
+        }
+    }
+
+    let mut index = Data::Size::zero();
+    for addr in registers(data) {
+        if !jump_path.iter().any(|entry| entry.in_register && entry.register_len == index) {
+            roots.push(addr);
+        }
+
+        index += Data::Size::one();
+    }
+
+    for i in data.get_instruction_iter() {
+        if let Some((instruction, Some(addr))) = data.get_instruction(i) {
+            if instruction_has_data_operand(instruction) {
+                roots.push(addr);
+            }
+        }
+    }
+
+    roots
+}
+
+pub(crate) fn instruction_has_data_operand(instruction: Instruction) -> bool {
+    matches!(instruction, Instruction::Put | Instruction::Resolve)
+}
+
+/// Addresses reachable from the roots, along with the number of addresses first marked from each root.
+/// Roots are marked in order, so addresses reachable from more than one root are counted for the first.
+pub(crate) fn mark_reachable<Data: GarnishData>(
+    roots: &[Data::Size],
+    data: &Data,
+) -> Result<MarkedByRoot<Data::Size>, Data::Error> {
+    let (marked, _, counts) = mark_reachable_internal(roots, data)?;
+    Ok((marked, counts))
+}
+
+/// Marked addresses along with the number first marked from each root.
+pub(crate) type MarkedByRoot<Size> = (AddressSet<Size>, Vec<usize>);
+
+/// Marked addresses, the first value found reachable from its own children if any, and the number of addresses first marked from each root.
+type Marked<Size> = (AddressSet<Size>, Option<Size>, Vec<usize>);
+
+fn mark_reachable_internal<Data: GarnishData>(
+    roots: &[Data::Size],
+    data: &Data,
+) -> Result<Marked<Data::Size>, Data::Error> {
+    let mut marked = AddressSet::new();
+    let mut cycle = None;
+    let mut counts = vec![];
+
+    for root in roots {
+        let mut visitor = MarkVisitor { marked: &mut marked, count: 0, cycle: None };
+        walk_data(root.clone(), data, &mut visitor)?;
+        cycle = cycle.or(visitor.cycle);
+        counts.push(visitor.count);
+    }
+
+    Ok((marked, cycle, counts))
+}
+
+/// Copies all data reachable from the roots of one runtime into another, leaving behind any data that is no longer referenced.
+///
+/// Live data is copied in order of its original address, with any values it refers to copied first.
+/// Value stack, registers, jump path, instructions and jump table are then rebuilt in `to` using the new addresses,
+/// and the instruction cursor is carried over.
+/// `to` is expected to be empty.
+///
+/// Returns [`UtilityError::Cycle`] before copying anything if a live value is reachable from its own children.
+/// Otherwise returns map of old addresses to their new addresses.
+pub fn collect_garbage<Data: GarnishData + Clone>(
+    from: &Data,
+    to: &mut Data,
+) -> Result<AddressMap<Data::Size>, UtilityError<Data::Error>> {
+    collect_garbage_internal(from, to, None, None)
+}
+
+pub fn collect_garbage_with_handlers<Data: GarnishData + Clone>(
+    from: &Data,
+    to: &mut Data,
+    custom_handler: CloneHandler<Data>,
+    invalid_handler: CloneHandler<Data>,
+) -> Result<AddressMap<Data::Size>, UtilityError<Data::Error>> {
+    collect_garbage_internal(from, to, Some(custom_handler), Some(invalid_handler))
+}
+
+fn collect_garbage_internal<Data: GarnishData + Clone>(
+    from: &Data,
+    to: &mut Data,
+    custom_handler: Option<CloneHandler<Data>>,
+    invalid_handler: Option<CloneHandler<Data>>,
+) -> Result<AddressMap<Data::Size>, UtilityError<Data::Error>> {
+    let jump_path = read_jump_path(from);
+    let (marked, cycle, _) = mark_reachable_internal(&root_addresses_internal(from, &jump_path), from)?;
+    if let Some(addr) = cycle {
+        return Err(UtilityError::Cycle(format!("value at {} is reachable from its own children", addr)));
+    }
+
+    let mut map = AddressMap::new();
+    clone_each_internal(marked.iter().cloned(), from, to, custom_handler, invalid_handler, &mut map)?;
+
+    for i in from.get_instruction_iter() {
+        if let Some((instruction, operand)) = from.get_instruction(i) {
+            let operand = match operand {
+                Some(addr) if instruction_has_data_operand(instruction) => Some(relocate(addr, &map)),
+                operand => operand,
+            };
+
+            to.push_instruction(instruction, operand)?;
+        }
+    }
+
+    for i in from.get_jump_table_iter() {
+        if let Some(point) = from.get_jump_point(i) {
+            to.push_jump_point(point)?;
+        }
+    }
+
+    for i in from.get_value_iter() {
+        if let Some(addr) = from.get_value(i) {
+            to.push_value_stack(relocate(addr, &map))?;
+        }
+    }
+
+    push_registers_and_jump_path(&registers(from), &jump_path, to, |addr| relocate(addr, &map))?;
+
+    to.set_instruction_cursor(from.get_instruction_cursor())?;
+
+    Ok(map)
+}
+
+fn relocate<Size: PartialOrd + Clone>(addr: Size, map: &AddressMap<Size>) -> Size {
+    map.get(&addr).cloned().unwrap_or(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleData, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, Instruction};
+    use crate::data::{collect_garbage, read_jump_path, root_addresses, JumpPathEntry};
+    use crate::UtilityError;
+
+    #[test]
+    fn roots() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d3 = data.add_number(SimpleNumber::Integer(30)).unwrap();
+        let d4 = data.add_number(SimpleNumber::Integer(40)).unwrap();
+
+        data.push_value_stack(d1).unwrap();
+        data.push_register(d2).unwrap();
+        data.push_instruction(Instruction::Put, Some(d3)).unwrap();
+        data.push_instruction(Instruction::JumpTo, Some(d4)).unwrap();
+
+        assert_eq!(root_addresses(&data), vec![d1, d2, d3]);
+    }
+
+    #[test]
+    fn collect() {
+        let mut from = SimpleGarnishData::new();
+        let _garbage1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d1 = from.add_number(SimpleNumber::Integer(20)).unwrap();
+        let _garbage2 = from.add_number(SimpleNumber::Integer(30)).unwrap();
+        let d2 = from.add_symbol(100).unwrap();
+        let d3 = from.add_pair((d2, d1)).unwrap();
+        let _garbage3 = from.add_pair((d1, d2)).unwrap();
+        let d4 = from.add_number(SimpleNumber::Integer(40)).unwrap();
+
+        from.push_instruction(Instruction::Put, Some(d4)).unwrap();
+        from.push_instruction(Instruction::JumpTo, Some(0)).unwrap();
+        from.push_jump_point(1).unwrap();
+        from.push_value_stack(d3).unwrap();
+        from.push_register(d1).unwrap();
+        from.set_instruction_cursor(1).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let map = collect_garbage(&from, &mut to).unwrap();
+
+        assert_eq!(map.len(), 4);
+        assert_eq!(to.get_data_len(), 7);
+        assert_eq!(map.get(&d1), Some(&3));
+        assert_eq!(map.get(&d2), Some(&4));
+        assert_eq!(map.get(&d3), Some(&5));
+        assert_eq!(map.get(&d4), Some(&6));
+
+        assert_eq!(to.get_data().get(5).unwrap().as_pair().unwrap(), (4, 3));
+        assert_eq!(to.get_value(0), Some(5));
+        assert_eq!(to.get_register(0), Some(3));
+        assert_eq!(to.get_instruction(0), Some((Instruction::Put, Some(6))));
+        assert_eq!(to.get_instruction(1), Some((Instruction::JumpTo, Some(0))));
+        assert_eq!(to.get_jump_point(0), Some(1));
+        assert_eq!(to.get_instruction_cursor(), 1);
+    }
+
+    #[test]
+    fn collect_list() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let _garbage = from.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d2 = from.add_number(SimpleNumber::Integer(30)).unwrap();
+        from.start_list(2).unwrap();
+        from.add_to_list(d1, false).unwrap();
+        from.add_to_list(d2, false).unwrap();
+        let list = from.end_list().unwrap();
+        from.push_value_stack(list).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let map = collect_garbage(&from, &mut to).unwrap();
+
+        assert_eq!(map.get(&list), Some(&5));
+        assert_eq!(to.get_data().get(5).unwrap().as_list().unwrap(), (vec![3, 4], vec![]));
+        assert_eq!(to.get_value(0), Some(5));
+    }
+
+    #[test]
+    fn collect_jump_path() {
+        let mut from = SimpleGarnishData::new();
+        let _garbage = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d1 = from.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d2 = from.add_number(SimpleNumber::Integer(30)).unwrap();
+        from.push_register(d1).unwrap();
+        from.push_jump_path(5).unwrap();
+        from.push_register(d2).unwrap();
+        from.push_jump_path(8).unwrap();
+
+        assert_eq!(
+            read_jump_path(&from),
+            vec![
+                JumpPathEntry { point: 5, register_len: 1, in_register: true },
+                JumpPathEntry { point: 8, register_len: 3, in_register: true }
+            ]
+        );
+        assert_eq!(root_addresses(&from), vec![d1, d2]);
+
+        let mut to = SimpleGarnishData::new();
+        let map = collect_garbage(&from, &mut to).unwrap();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(to.get_register_len(), 4);
+        assert_eq!(to.get_register(0), Some(3));
+        assert_eq!(to.get_register(2), Some(4));
+        assert_eq!(read_jump_path(&to), read_jump_path(&from));
+
+        assert_eq!(to.pop_jump_path(), Some(8));
+        assert_eq!(to.pop_register().unwrap(), Some(4));
+        assert_eq!(to.pop_jump_path(), Some(5));
+        assert_eq!(to.pop_register().unwrap(), Some(3));
+    }
+
+    #[test]
+    fn collect_cycle_is_error() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let cat = from.get_data_len();
+        from.get_data_mut().push(SimpleData::Concatenation(d1, cat + 1));
+        from.get_data_mut().push(SimpleData::Pair(d1, cat));
+        from.push_value_stack(cat).unwrap();
+        from.push_jump_path(2).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let result = collect_garbage(&from, &mut to);
+
+        assert!(matches!(result, Err(UtilityError::Cycle(_))));
+        assert_eq!(to.get_data_len(), SimpleGarnishData::new().get_data_len());
+    }
+
+    #[test]
+    fn collect_forward_reference() {
+        let mut from = SimpleGarnishData::new();
+        let pair = from.get_data_len();
+        from.get_data_mut().push(SimpleData::Pair(pair + 1, pair + 2));
+        from.add_number(SimpleNumber::Integer(10)).unwrap();
+        from.add_number(SimpleNumber::Integer(20)).unwrap();
+        from.push_value_stack(pair).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let map = collect_garbage(&from, &mut to).unwrap();
+
+        let (left, right) = to.get_pair(*map.get(&pair).unwrap()).unwrap();
+        assert_eq!(to.get_number(left).unwrap(), SimpleNumber::Integer(10));
+        assert_eq!(to.get_number(right).unwrap(), SimpleNumber::Integer(20));
+    }
+}
@@ -1,7 +1,17 @@
 use garnish_lang_traits::{GarnishData, GarnishDataType, TypeConstants};
+use crate::visitor::{children, is_compound, traverse, Step, Traversal};
+use crate::{find_cycle, AddressMap, UtilityError, VisitAction};
 
 pub type CloneHandler<Data> = fn(<Data as GarnishData>::Size, &Data, &mut Data) -> Result<<Data as GarnishData>::Size, <Data as GarnishData>::Error>;
 
+/// Discards everything added to the store after the given data length, along with any list left unfinished.
+/// [`GarnishData`] has no way to remove data, so this is provided by the caller for their implementation.
+pub type RollbackHandler<Data> = fn(&mut Data, <Data as GarnishData>::Size) -> Result<(), <Data as GarnishData>::Error>;
+
+/// Copies the value at the given address, along with every value it refers to, into `to` and returns the new address.
+///
+/// Values shared within the value are copied once. A reference back to a value from one of its own children is copied as Unit,
+/// use [`clone_data_checked`] to get an error instead.
 pub fn clone_data<Data: GarnishData>(
     data_addr: Data::Size,
     from: &Data,
@@ -13,6 +23,7 @@ pub fn clone_data<Data: GarnishData>(
         to,
         None,
         None,
+        None,
     )
 }
 
@@ -27,7 +38,8 @@ pub fn clone_data_with_custom_handler<Data: GarnishData>(
         from,
         to,
         Some(custom_handler),
-        None
+        None,
+        None,
     )
 }
 
@@ -43,6 +55,7 @@ pub fn clone_data_with_invalid_handler<Data: GarnishData>(
         to,
         None,
         Some(invalid_handler),
+        None,
     )
 }
 
@@ -59,116 +72,359 @@ pub fn clone_data_with_handlers<Data: GarnishData>(
         to,
         Some(custom_handler),
         Some(invalid_handler),
+        None,
+    )
+}
+
+/// Clone that records each copied address in the given map, reusing existing copies for any address already in it.
+///
+/// Passing the same map to multiple calls shares copies between them, so values referenced more than once are only copied once.
+pub fn clone_data_with_map<Data: GarnishData>(
+    data_addr: Data::Size,
+    from: &Data,
+    to: &mut Data,
+    map: &mut AddressMap<Data::Size>,
+) -> Result<Data::Size, Data::Error> {
+    clone_data_with_handlers_internal(
+        data_addr,
+        from,
+        to,
+        None,
+        None,
+        Some(map),
+    )
+}
+
+pub fn clone_data_with_handlers_and_map<Data: GarnishData>(
+    data_addr: Data::Size,
+    from: &Data,
+    to: &mut Data,
+    custom_handler: CloneHandler<Data>,
+    invalid_handler: CloneHandler<Data>,
+    map: &mut AddressMap<Data::Size>,
+) -> Result<Data::Size, Data::Error> {
+    clone_data_with_handlers_internal(
+        data_addr,
+        from,
+        to,
+        Some(custom_handler),
+        Some(invalid_handler),
+        Some(map),
     )
 }
 
-fn clone_data_with_handlers_internal<Data: GarnishData>(
+/// Clone for untrusted data, returning [`UtilityError::Cycle`] without adding anything to `to` if the value refers back to itself.
+///
+/// Values shared within the cloned value are copied once and copying never recurses, so work is bounded by the number of reachable values.
+pub fn clone_data_checked<Data: GarnishData>(
+    data_addr: Data::Size,
+    from: &Data,
+    to: &mut Data,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    if let Some(addr) = find_cycle(data_addr.clone(), from)? {
+        return Err(UtilityError::Cycle(format!("value at {} is reachable from its own children", addr)));
+    }
+
+    let mut map = AddressMap::new();
+    clone_each_internal([data_addr.clone()], from, to, None, None, &mut map)?;
+
+    match map.get(&data_addr) {
+        Some(addr) => Ok(addr.clone()),
+        None => Ok(to.add_unit()?),
+    }
+}
+
+/// Clone that leaves `to` as it was if copying fails, by passing the data length from before the copy to `rollback`.
+///
+/// The original error is returned after rolling back, even if the rollback fails too.
+pub fn clone_data_transactional<Data: GarnishData>(
+    data_addr: Data::Size,
+    from: &Data,
+    to: &mut Data,
+    rollback: RollbackHandler<Data>,
+) -> Result<Data::Size, Data::Error> {
+    let len = to.get_data_len();
+    clone_data_with_handlers_internal(data_addr, from, to, None, None, None).inspect_err(|_| {
+        // the copy's error is the cause, a failed rollback is secondary
+        let _ = rollback(to, len);
+    })
+}
+
+pub fn clone_data_transactional_with_handlers<Data: GarnishData>(
+    data_addr: Data::Size,
+    from: &Data,
+    to: &mut Data,
+    custom_handler: CloneHandler<Data>,
+    invalid_handler: CloneHandler<Data>,
+    rollback: RollbackHandler<Data>,
+) -> Result<Data::Size, Data::Error> {
+    let len = to.get_data_len();
+    clone_data_with_handlers_internal(data_addr, from, to, Some(custom_handler), Some(invalid_handler), None).inspect_err(|_| {
+        let _ = rollback(to, len);
+    })
+}
+
+pub(crate) fn clone_data_with_handlers_internal<Data: GarnishData>(
     data_addr: Data::Size,
     from: &Data,
     to: &mut Data,
     custom_handler: Option<CloneHandler<Data>>,
     invalid_handler: Option<CloneHandler<Data>>, // to be implemented
+    map: Option<&mut AddressMap<Data::Size>>,
 ) -> Result<Data::Size, Data::Error> {
-    match from.get_data_type(data_addr.clone())? {
+    let mut local_map = AddressMap::new();
+    let map = match map {
+        Some(map) => map,
+        None => &mut local_map,
+    };
+
+    let mut traversal = CloneTraversal { from, to, custom_handler, invalid_handler, map, strict: false, cycle: None };
+    traverse([(data_addr.clone(), ())], &mut traversal)?;
+
+    match traversal.map.get(&data_addr) {
+        Some(addr) => Ok(addr.clone()),
+        None => traversal.to.add_unit(),
+    }
+}
+
+/// Copies each of the given addresses into the map in order, with any values they refer to copied first.
+/// Returns [`UtilityError::Cycle`] if a value is reachable from its own children.
+pub(crate) fn clone_each_internal<Data: GarnishData>(
+    addrs: impl IntoIterator<Item = Data::Size>,
+    from: &Data,
+    to: &mut Data,
+    custom_handler: Option<CloneHandler<Data>>,
+    invalid_handler: Option<CloneHandler<Data>>,
+    map: &mut AddressMap<Data::Size>,
+) -> Result<(), UtilityError<Data::Error>> {
+    let mut traversal = CloneTraversal { from, to, custom_handler, invalid_handler, map, strict: true, cycle: None };
+    traverse(addrs.into_iter().map(|addr| (addr, ())), &mut traversal)?;
+
+    match traversal.cycle {
+        Some(addr) => Err(UtilityError::Cycle(format!("value at {} is reachable from its own children", addr))),
+        None => Ok(()),
+    }
+}
+
+/// Copies values while they're traversed, recording each copy in the map.
+/// Values are copied once all the values they refer to have been, so copying never recurses.
+///
+/// A value reached from its own children stops the traversal when strict, otherwise the reference back to it is copied as Unit.
+struct CloneTraversal<'a, Data: GarnishData> {
+    from: &'a Data,
+    to: &'a mut Data,
+    custom_handler: Option<CloneHandler<Data>>,
+    invalid_handler: Option<CloneHandler<Data>>,
+    map: &'a mut AddressMap<Data::Size>,
+    strict: bool,
+    cycle: Option<Data::Size>,
+}
+
+/// Type of a compound value along with the addresses it refers to.
+type CloneLeave<Size> = (GarnishDataType, Vec<Size>);
+
+impl<'a, Data: GarnishData> Traversal for CloneTraversal<'a, Data> {
+    type Node = Data::Size;
+    type Context = ();
+    type Leave = CloneLeave<Data::Size>;
+    type Error = Data::Error;
+
+    fn enter(&mut self, addr: Data::Size, _context: ()) -> Result<Step<Data::Size, (), CloneLeave<Data::Size>>, Data::Error> {
+        if self.map.contains(&addr) {
+            return Ok(Step::Leaf);
+        }
+
+        let data_type = self.from.get_data_type(addr.clone())?;
+        if is_compound(data_type) {
+            let children = children(addr, data_type, self.from)?;
+            return Ok(Step::Children(children.iter().cloned().map(|child| (child, ())).collect(), (data_type, children)));
+        }
+
+        let new_addr = clone_value(addr.clone(), data_type, self.from, self.to, self.custom_handler, self.invalid_handler)?;
+        self.map.insert(addr, new_addr);
+
+        Ok(Step::Leaf)
+    }
+
+    fn leave(&mut self, addr: Data::Size, (data_type, children): CloneLeave<Data::Size>) -> Result<VisitAction, Data::Error> {
+        let mut copies = vec![];
+        for child in children {
+            copies.push(match self.map.get(&child) {
+                Some(copy) => copy.clone(),
+                None => self.to.add_unit()?,
+            });
+        }
+
+        let new_addr = match (data_type, copies.as_slice()) {
+            (GarnishDataType::Pair, [left, right]) => self.to.add_pair((left.clone(), right.clone()))?,
+            (GarnishDataType::Range, [left, right]) => self.to.add_range(left.clone(), right.clone())?,
+            (GarnishDataType::Concatenation, [left, right]) => self.to.add_concatenation(left.clone(), right.clone())?,
+            (GarnishDataType::Slice, [left, right]) => self.to.add_slice(left.clone(), right.clone())?,
+            _ => {
+                let len = self.from.get_list_len(addr.clone())?;
+                let mut items = vec![];
+                for copy in copies {
+                    let is_association = is_association(copy.clone(), self.to)?;
+                    items.push((copy, is_association));
+                }
+
+                self.to.start_list(len)?;
+                for (copy, is_association) in items {
+                    self.to.add_to_list(copy, is_association)?;
+                }
+                self.to.end_list()?
+            }
+        };
+
+        self.map.insert(addr, new_addr);
+        Ok(VisitAction::Continue)
+    }
+
+    fn cycle(&mut self, addr: &Data::Size, _context: &()) -> Result<VisitAction, Data::Error> {
+        self.cycle.get_or_insert(addr.clone());
+        Ok(match self.strict {
+            true => VisitAction::Stop,
+            false => VisitAction::Continue,
+        })
+    }
+}
+
+/// Copies a value that doesn't refer to other values, or Unit for one that does.
+pub(crate) fn clone_value<Data: GarnishData>(
+    data_addr: Data::Size,
+    data_type: GarnishDataType,
+    from: &Data,
+    to: &mut Data,
+    custom_handler: Option<CloneHandler<Data>>,
+    invalid_handler: Option<CloneHandler<Data>>,
+) -> Result<Data::Size, Data::Error> {
+    match data_type {
         GarnishDataType::Invalid => match invalid_handler {
             None => to.add_unit(),
-            Some(handler) => handler(data_addr.clone(), from, to)
+            Some(handler) => handler(data_addr, from, to)
         }
         GarnishDataType::Custom => match custom_handler {
             None => to.add_unit(),
-            Some(handler) => handler(data_addr.clone(), from, to)
+            Some(handler) => handler(data_addr, from, to)
         }
-        GarnishDataType::Unit => to.add_unit(),
-        GarnishDataType::Number => to.add_number(from.get_number(data_addr.clone())?),
-        GarnishDataType::Type => to.add_type(from.get_type(data_addr.clone())?),
-        GarnishDataType::Char => to.add_char(from.get_char(data_addr.clone())?),
+        GarnishDataType::Number => to.add_number(from.get_number(data_addr)?),
+        GarnishDataType::Type => to.add_type(from.get_type(data_addr)?),
+        GarnishDataType::Char => to.add_char(from.get_char(data_addr)?),
         GarnishDataType::CharList => {
             let len = from.get_char_list_len(data_addr.clone())?;
             let iter =
                 Data::make_number_iterator_range(Data::Number::zero(), Data::size_to_number(len));
-            to.start_char_list()?;
+            // read every item before starting the list, a failed read would otherwise leave an open char list in `to`
+            let mut items = vec![];
             for i in iter {
-                to.add_to_char_list(from.get_char_list_item(data_addr.clone(), i)?)?;
+                items.push(from.get_char_list_item(data_addr.clone(), i)?);
+            }
+
+            to.start_char_list()?;
+            for item in items {
+                to.add_to_char_list(item)?;
             }
 
             to.end_char_list()
         }
-        GarnishDataType::Byte => to.add_byte(from.get_byte(data_addr.clone())?),
+        GarnishDataType::Byte => to.add_byte(from.get_byte(data_addr)?),
         GarnishDataType::ByteList => {
             let len = from.get_byte_list_len(data_addr.clone())?;
             let iter =
                 Data::make_number_iterator_range(Data::Number::zero(), Data::size_to_number(len));
-            to.start_byte_list()?;
-            for i in iter {
-                to.add_to_byte_list(from.get_byte_list_item(data_addr.clone(), i)?)?;
-            }
-
-            to.end_byte_list()
-        }
-        GarnishDataType::Symbol => to.add_symbol(from.get_symbol(data_addr.clone())?),
-        GarnishDataType::Pair => from.get_pair(data_addr.clone()).and_then(|(left, right)| {
-            let to_left = clone_data_with_handlers_internal(left, from, to, custom_handler, invalid_handler)?;
-            let to_right = clone_data_with_handlers_internal(right, from, to, custom_handler, invalid_handler)?;
-            to.add_pair((to_left, to_right))
-        }),
-        GarnishDataType::Range => from.get_range(data_addr.clone()).and_then(|(left, right)| {
-            let to_left = clone_data_with_handlers_internal(left, from, to, custom_handler, invalid_handler)?;
-            let to_right = clone_data_with_handlers_internal(right, from, to, custom_handler, invalid_handler)?;
-            to.add_range(to_left, to_right)
-        }),
-        GarnishDataType::Concatenation => {
-            from.get_concatenation(data_addr.clone()).and_then(|(left, right)| {
-                let to_left = clone_data_with_handlers_internal(left, from, to, custom_handler, invalid_handler)?;
-                let to_right = clone_data_with_handlers_internal(right, from, to, custom_handler, invalid_handler)?;
-                to.add_concatenation(to_left, to_right)
-            })
-        }
-        GarnishDataType::Slice => from.get_slice(data_addr.clone()).and_then(|(left, right)| {
-            let to_left = clone_data_with_handlers_internal(left, from, to, custom_handler, invalid_handler)?;
-            let to_right = clone_data_with_handlers_internal(right, from, to, custom_handler, invalid_handler)?;
-            to.add_slice(to_left, to_right)
-        }),
-        GarnishDataType::List => {
-            let len = from.get_list_len(data_addr.clone())?;
-            let iter =
-                Data::make_number_iterator_range(Data::Number::zero(), Data::size_to_number(len.clone()));
-
+            // same as char lists
             let mut items = vec![];
             for i in iter {
-                let addr = from
-                    .get_list_item(data_addr.clone(), i)
-                    .and_then(|addr| clone_data_with_handlers_internal(addr, from, to, custom_handler, invalid_handler))?;
-                let is_association = match to.get_data_type(addr.clone())? {
-                    GarnishDataType::Pair => {
-                        let (left, _right) = to.get_pair(addr.clone())?;
-                        match to.get_data_type(left)? {
-                            GarnishDataType::Symbol => true,
-                            _ => false
-                        }
-                    }
-                    _ => false
-                };
-                items.push((addr, is_association));
+                items.push(from.get_byte_list_item(data_addr.clone(), i)?);
             }
 
-            to.start_list(len.clone())?;
-            for (addr, is_association) in items {
-                to.add_to_list(addr, is_association)?;
+            to.start_byte_list()?;
+            for item in items {
+                to.add_to_byte_list(item)?;
             }
-            to.end_list()
+
+            to.end_byte_list()
         }
-        GarnishDataType::Expression => to.add_expression(from.get_expression(data_addr.clone())?),
-        GarnishDataType::External => to.add_external(from.get_external(data_addr.clone())?),
+        GarnishDataType::Symbol => to.add_symbol(from.get_symbol(data_addr)?),
+        GarnishDataType::Expression => to.add_expression(from.get_expression(data_addr)?),
+        GarnishDataType::External => to.add_external(from.get_external(data_addr)?),
         GarnishDataType::True => to.add_true(),
         GarnishDataType::False => to.add_false(),
+        _ => to.add_unit(),
     }
 }
 
+/// Clones every value on the value stack of `from` and pushes the copies onto the value stack of `to`, bottom first.
+///
+/// Values shared between entries are only copied once. Returns map of copied addresses.
+pub fn clone_value_stack<Data: GarnishData>(
+    from: &Data,
+    to: &mut Data,
+) -> Result<AddressMap<Data::Size>, Data::Error> {
+    let values = from.get_value_iter().filter_map(|i| from.get_value(i)).collect::<Vec<Data::Size>>();
+    clone_values_internal(values, from, to)
+}
+
+/// Same as [`clone_value_stack`] but only the top `count` values are copied, keeping their order.
+pub fn clone_value_stack_top<Data: GarnishData>(
+    from: &Data,
+    to: &mut Data,
+    count: usize,
+) -> Result<AddressMap<Data::Size>, Data::Error> {
+    let mut values = from.get_value_iter().rev().take(count).filter_map(|i| from.get_value(i)).collect::<Vec<Data::Size>>();
+    values.reverse();
+    clone_values_internal(values, from, to)
+}
+
+fn clone_values_internal<Data: GarnishData>(
+    values: Vec<Data::Size>,
+    from: &Data,
+    to: &mut Data,
+) -> Result<AddressMap<Data::Size>, Data::Error> {
+    let mut map = AddressMap::new();
+    for addr in values {
+        let new_addr = clone_data_with_map(addr, from, to, &mut map)?;
+        to.push_value_stack(new_addr)?;
+    }
+
+    Ok(map)
+}
+
+pub(crate) fn is_association<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<bool, Data::Error> {
+    Ok(match data.get_data_type(addr.clone())? {
+        GarnishDataType::Pair => {
+            let (left, _right) = data.get_pair(addr)?;
+            matches!(data.get_data_type(left)?, GarnishDataType::Symbol)
+        }
+        _ => false
+    })
+}
+
+/// Creates a list of the given items, marking any symbol keyed pairs as associations.
+pub(crate) fn add_list<Data: GarnishData>(items: Vec<Data::Size>, data: &mut Data) -> Result<Data::Size, Data::Error> {
+    let mut len = Data::Size::zero();
+    let mut list_items = vec![];
+    for item in items {
+        let is_association = is_association(item.clone(), data)?;
+        list_items.push((item, is_association));
+        len += Data::Size::one();
+    }
+
+    data.start_list(len)?;
+    for (item, is_association) in list_items {
+        data.add_to_list(item, is_association)?;
+    }
+    data.end_list()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::data::{clone_data, clone_data_with_custom_handler, clone_data_with_handlers, clone_data_with_invalid_handler};
-    use garnish_lang_simple_data::{SimpleData, SimpleGarnishData, SimpleNumber};
+    use crate::data::{clone_data, clone_data_checked, clone_data_transactional, clone_data_transactional_with_handlers, clone_data_with_custom_handler, clone_data_with_handlers, clone_data_with_invalid_handler, clone_data_with_map, clone_value_stack, clone_value_stack_top};
+    use crate::fault::FaultyData;
+    use crate::{read_bytes, read_string, AddressMap, UtilityError};
+    use garnish_lang_simple_data::{DataError, SimpleData, SimpleDataList, SimpleGarnishData, SimpleNumber};
     use garnish_lang_traits::{GarnishData, GarnishDataType};
+    use crate::mock::MockData;
     use crate::data::copy::test_data_impl::TestData;
 
     #[test]
@@ -292,6 +548,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn copy_char_list_reads_back() {
+        let mut from = SimpleGarnishData::new();
+        let addr = from.parse_add_char_list("\"stuff\"").unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let new_addr = clone_data(addr, &from, &mut to).unwrap();
+
+        assert_eq!(read_string(new_addr, &to).unwrap(), "stuff");
+    }
+
     #[test]
     fn copy_byte() {
         let mut from = SimpleGarnishData::new();
@@ -327,6 +594,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn copy_byte_list_reads_back() {
+        let mut from = SimpleGarnishData::new();
+        let addr = from.parse_add_byte_list("''100 150 200''").unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let new_addr = clone_data(addr, &from, &mut to).unwrap();
+
+        assert_eq!(read_bytes(new_addr, &to).unwrap(), vec![100, 150, 200]);
+    }
+
+    #[test]
+    fn copy_char_list_read_error_starts_no_list() {
+        let mut from = MockData::wrap(SimpleGarnishData::new());
+        let addr = from.parse_add_char_list("\"abc\"").unwrap();
+        from.fail_nth("get_char_list_item", 1, DataError::from("failed".to_string()));
+
+        let mut to = MockData::wrap(SimpleGarnishData::new());
+
+        assert!(clone_data(addr, &from, &mut to).is_err());
+        assert_eq!(to.call_count("start_char_list"), 0);
+        assert_eq!(to.call_count("add_to_char_list"), 0);
+    }
+
+    #[test]
+    fn copy_byte_list_read_error_starts_no_list() {
+        let mut from = MockData::wrap(SimpleGarnishData::new());
+        let addr = from.parse_add_byte_list("''100 150 200''").unwrap();
+        from.fail_nth("get_byte_list_item", 1, DataError::from("failed".to_string()));
+
+        let mut to = MockData::wrap(SimpleGarnishData::new());
+
+        assert!(clone_data(addr, &from, &mut to).is_err());
+        assert_eq!(to.call_count("start_byte_list"), 0);
+        assert_eq!(to.call_count("add_to_byte_list"), 0);
+    }
+
     #[test]
     fn copy_range() {
         let mut from = SimpleGarnishData::new();
@@ -699,6 +1003,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn copy_with_map_reuses_copies() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_symbol(100).unwrap();
+        let d2 = from.add_number(SimpleNumber::Integer(200)).unwrap();
+        let d3 = from.add_pair((d1, d2)).unwrap();
+        let d4 = from.add_concatenation(d3, d3).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let mut map = AddressMap::new();
+
+        let new_addr = clone_data_with_map(d4, &from, &mut to, &mut map).unwrap();
+        let pair_addr = clone_data_with_map(d3, &from, &mut to, &mut map).unwrap();
+
+        assert_eq!(new_addr, 6);
+        assert_eq!(pair_addr, 5);
+        assert_eq!(to.get_data_len(), 7);
+        assert_eq!(to.get_data().get(6).unwrap().as_concatenation().unwrap(), (5, 5));
+        assert_eq!(map.get(&d4), Some(&6));
+    }
+
+    fn make_value_stack() -> SimpleGarnishData {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = from.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d3 = from.add_pair((d1, d2)).unwrap();
+        let d4 = from.add_number(SimpleNumber::Integer(30)).unwrap();
+
+        from.push_value_stack(d1).unwrap();
+        from.push_value_stack(d3).unwrap();
+        from.push_value_stack(d4).unwrap();
+
+        from
+    }
+
+    #[test]
+    fn copy_value_stack() {
+        let from = make_value_stack();
+
+        let mut to = SimpleGarnishData::new();
+        to.push_value_stack(0).unwrap();
+
+        let map = clone_value_stack(&from, &mut to).unwrap();
+
+        assert_eq!(map.len(), 4);
+        assert_eq!(to.get_value_stack_len(), 4);
+        assert_eq!(to.get_value(0), Some(0));
+        assert_eq!(to.get_value(1), Some(3));
+        assert_eq!(to.get_value(2), Some(5));
+        assert_eq!(to.get_value(3), Some(6));
+        assert_eq!(to.get_data().get(5).unwrap().as_pair().unwrap(), (3, 4));
+        assert_eq!(to.get_data_len(), 7);
+    }
+
+    #[test]
+    fn copy_value_stack_top() {
+        let from = make_value_stack();
+
+        let mut to = SimpleGarnishData::new();
+        let map = clone_value_stack_top(&from, &mut to, 2).unwrap();
+
+        assert_eq!(map.len(), 4);
+        assert_eq!(to.get_value_stack_len(), 2);
+        assert_eq!(to.get_value(0), Some(5));
+        assert_eq!(to.get_value(1), Some(6));
+        assert_eq!(
+            to.get_data().get(6).unwrap().as_number().unwrap(),
+            SimpleNumber::Integer(30)
+        );
+    }
+
+    #[test]
+    fn copy_value_stack_top_more_than_len() {
+        let from = make_value_stack();
+
+        let mut to = SimpleGarnishData::new();
+        clone_value_stack_top(&from, &mut to, 10).unwrap();
+
+        assert_eq!(to.get_value_stack_len(), 3);
+    }
+
     #[test]
     fn copy_slice() {
         let mut from = SimpleGarnishData::new();
@@ -753,8 +1138,164 @@ mod tests {
             SimpleNumber::Integer(300)
         );
     }
-}
 
+    // SimpleGarnishData can't remove data, so rollback is recorded in the registers instead
+    fn record_rollback(to: &mut FaultyData<SimpleGarnishData>, len: usize) -> Result<(), DataError> {
+        to.push_register(len)
+    }
+
+    fn truncate_rollback(to: &mut FaultyData<SimpleGarnishData>, len: usize) -> Result<(), DataError> {
+        let data = to.inner_mut().get_data_mut();
+        let mut kept = SimpleDataList::new();
+        for i in 0..len {
+            kept.push(data.get(i).cloned().ok_or_else(|| DataError::from("bad length".to_string()))?);
+        }
+        *data = kept;
+        Ok(())
+    }
+
+    fn make_pair_list() -> FaultyData<SimpleGarnishData> {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = from.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d3 = from.add_pair((d1, d2)).unwrap();
+        from.start_list(1).unwrap();
+        from.add_to_list(d3, false).unwrap();
+        from.end_list().unwrap();
+        FaultyData::new(from)
+    }
+
+    #[test]
+    fn copy_transactional() {
+        let from = make_pair_list();
+        let mut to = FaultyData::new(SimpleGarnishData::new());
+
+        let new_addr = clone_data_transactional(6, &from, &mut to, record_rollback).unwrap();
+
+        assert_eq!(new_addr, 6);
+        assert_eq!(to.get_register(0), None);
+    }
+
+    #[test]
+    fn copy_transactional_rolls_back() {
+        let from = make_pair_list();
+        let mut to = FaultyData::new(SimpleGarnishData::new());
+        to.add_number(SimpleNumber::Integer(30)).unwrap();
+        to.fail_method("add_pair", || DataError::from("fault".to_string()));
+
+        let result = clone_data_transactional(6, &from, &mut to, record_rollback);
+
+        assert_eq!(result.unwrap_err().to_string(), "fault");
+        assert_eq!(to.get_register(0), Some(4));
+    }
+
+    #[test]
+    fn copy_transactional_restores_data() {
+        let from = make_pair_list();
+        let mut to = FaultyData::new(SimpleGarnishData::new());
+        to.add_number(SimpleNumber::Integer(30)).unwrap();
+        let before = to.inner().get_data().clone();
+        to.fail_method("add_pair", || DataError::from("fault".to_string()));
+
+        let result = clone_data_transactional(6, &from, &mut to, truncate_rollback);
+
+        assert_eq!(result.unwrap_err().to_string(), "fault");
+        assert_eq!(to.get_data_len(), 4);
+        assert_eq!(to.inner().get_data(), &before);
+    }
+
+    #[test]
+    fn copy_transactional_rollback_error() {
+        let mut from = FaultyData::new(SimpleGarnishData::new());
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let mut to = FaultyData::new(SimpleGarnishData::new());
+        to.fail_method("add_number", || DataError::from("fault".to_string()));
+
+        let result = clone_data_transactional_with_handlers(
+            d1, &from, &mut to,
+            |_, _, to| to.add_unit(),
+            |_, _, to| to.add_unit(),
+            |_, _| Err(DataError::from("rollback".to_string())),
+        );
+
+        assert_eq!(result.unwrap_err().to_string(), "fault");
+    }
+
+    #[test]
+    fn copy_checked() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = from.add_pair((d1, d1)).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+
+        let new_addr = clone_data_checked(d2, &from, &mut to).unwrap();
+
+        assert_eq!(to.get_data().get(new_addr).unwrap().as_pair().unwrap(), (3, 3));
+    }
+
+    #[test]
+    fn copy_checked_cycle() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = from.get_data_len();
+        from.get_data_mut().push(SimpleData::Pair(d1, d2));
+
+        let mut to = SimpleGarnishData::new();
+
+        let result = clone_data_checked(d2, &from, &mut to);
+
+        assert!(matches!(result, Err(UtilityError::Cycle(_))));
+        assert_eq!(to.get_data_len(), 3);
+    }
+
+    #[test]
+    fn copy_checked_deep_chain() {
+        let mut from = SimpleGarnishData::new();
+        let mut addr = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        for _ in 0..10_000 {
+            addr = from.add_pair((addr, addr)).unwrap();
+        }
+
+        let mut to = SimpleGarnishData::new();
+
+        let new_addr = clone_data_checked(addr, &from, &mut to).unwrap();
+
+        assert_eq!(new_addr, 10_003);
+        assert_eq!(to.get_data_len(), 10_004);
+    }
+
+    #[test]
+    fn copy_shared_list_once() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        from.start_list(1).unwrap();
+        from.add_to_list(d1, false).unwrap();
+        let list = from.end_list().unwrap();
+        let d2 = from.add_pair((list, list)).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+
+        let new_addr = clone_data(d2, &from, &mut to).unwrap();
+
+        assert_eq!(to.get_data().get(new_addr).unwrap().as_pair().unwrap(), (4, 4));
+        assert_eq!(to.get_data_len(), 6);
+    }
+
+    #[test]
+    fn copy_cycle_as_unit() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = from.get_data_len();
+        from.get_data_mut().push(SimpleData::Pair(d1, d2));
+
+        let mut to = SimpleGarnishData::new();
+
+        let new_addr = clone_data(d2, &from, &mut to).unwrap();
+
+        assert_eq!(to.get_data().get(new_addr).unwrap().as_pair().unwrap(), (3, 0));
+    }
+}
 #[cfg(test)]
 #[allow(unused)]
 mod test_data_impl {
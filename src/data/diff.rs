@@ -0,0 +1,584 @@
+use std::fmt::{Display, Formatter};
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::visitor::{traverse, Step, Traversal};
+use crate::{iterate_concatentation_checked, CyclePolicy, UtilityError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    /// Entry of the value stack.
+    Value(usize),
+    /// Entry of the registers.
+    Register(usize),
+    /// Item of a list or concatenation.
+    Index(usize),
+    /// Association of a list, by the name of its symbol.
+    Key(String),
+    /// Association of a list, by the value of its symbol when no name is known for it.
+    Symbol(String),
+    Left,
+    Right,
+    Start,
+    End,
+    SliceValue,
+    SliceRange,
+}
+
+impl Display for PathSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathSegment::Value(i) => write!(f, ".values[{}]", i),
+            PathSegment::Register(i) => write!(f, ".registers[{}]", i),
+            PathSegment::Index(i) => write!(f, "[{}]", i),
+            PathSegment::Key(key) => write!(f, ".{}", key),
+            PathSegment::Symbol(symbol) => write!(f, ".<sym {}>", symbol),
+            PathSegment::Left => f.write_str(".left"),
+            PathSegment::Right => f.write_str(".right"),
+            PathSegment::Start => f.write_str(".start"),
+            PathSegment::End => f.write_str(".end"),
+            PathSegment::SliceValue => f.write_str(".value"),
+            PathSegment::SliceRange => f.write_str(".range"),
+        }
+    }
+}
+
+/// Which side of a comparison a value is missing from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffSide {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffKind {
+    TypeMismatch(GarnishDataType, GarnishDataType),
+    /// Values of the same type that aren't equal, rendered with their [`Display`] implementation.
+    ValueMismatch(String, String),
+    LengthMismatch(usize, usize),
+    /// Association found on only one side. The path ends with the association's key.
+    MissingKey(String, DiffSide),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    pub path: Vec<PathSegment>,
+    pub kind: DiffKind,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("$")?;
+        for segment in self.path.iter() {
+            write!(f, "{}", segment)?;
+        }
+
+        match &self.kind {
+            DiffKind::TypeMismatch(left, right) => write!(f, ": type {:?} != {:?}", left, right),
+            DiffKind::ValueMismatch(left, right) => write!(f, ": {} != {}", left, right),
+            DiffKind::LengthMismatch(left, right) => write!(f, ": length {} != {}", left, right),
+            DiffKind::MissingKey(_, DiffSide::Left) => f.write_str(": missing from left"),
+            DiffKind::MissingKey(_, DiffSide::Right) => f.write_str(": missing from right"),
+        }
+    }
+}
+
+/// All differences found by [`diff_data`] or [`diff_stores`]. Displays one difference per line.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DataDiff {
+    pub differences: Vec<Difference>,
+}
+
+impl DataDiff {
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl Display for DataDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for difference in self.differences.iter() {
+            writeln!(f, "{}", difference)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Compares two values structurally, which may be in different stores.
+///
+/// Lists are compared by position, except for associations which are compared by key.
+/// Concatenations are compared by their items, so differently nested concatenations with the same items are equal.
+/// Invalid and Custom values can't be read through [`GarnishData`] and are always considered equal.
+/// Returns [`UtilityError::Cycle`] if a concatenation is reachable from its own children.
+///
+/// Association keys are shown by symbol value, see [`diff_data_with_symbol_names`] to show them by name.
+pub fn diff_data<Data: GarnishData>(
+    left_addr: Data::Size,
+    left: &Data,
+    right_addr: Data::Size,
+    right: &Data,
+) -> Result<DataDiff, UtilityError<Data::Error>> {
+    diff_data_with_symbol_names(left_addr, left, right_addr, right, |_| None)
+}
+
+/// Same as [`diff_data`], with association keys named by `names`.
+/// Keys it returns [`None`] for are shown by symbol value.
+pub fn diff_data_with_symbol_names<Data: GarnishData, Names: Fn(&Data::Symbol) -> Option<String>>(
+    left_addr: Data::Size,
+    left: &Data,
+    right_addr: Data::Size,
+    right: &Data,
+    names: Names,
+) -> Result<DataDiff, UtilityError<Data::Error>> {
+    let mut diff = DataDiff::default();
+    diff_internal(left_addr, left, right_addr, right, vec![], &names, &mut diff)?;
+    Ok(diff)
+}
+
+/// Compares the value stacks and registers of two stores.
+///
+/// Association keys are shown by symbol value, see [`diff_stores_with_symbol_names`] to show them by name.
+pub fn diff_stores<Data: GarnishData>(left: &Data, right: &Data) -> Result<DataDiff, UtilityError<Data::Error>> {
+    diff_stores_with_symbol_names(left, right, |_| None)
+}
+
+/// Same as [`diff_stores`], with association keys named by `names`.
+/// Keys it returns [`None`] for are shown by symbol value.
+pub fn diff_stores_with_symbol_names<Data: GarnishData, Names: Fn(&Data::Symbol) -> Option<String>>(
+    left: &Data,
+    right: &Data,
+    names: Names,
+) -> Result<DataDiff, UtilityError<Data::Error>> {
+    let mut diff = DataDiff::default();
+
+    let left_values = left.get_value_iter().filter_map(|i| left.get_value(i)).collect::<Vec<Data::Size>>();
+    let right_values = right.get_value_iter().filter_map(|i| right.get_value(i)).collect::<Vec<Data::Size>>();
+    if left_values.len() != right_values.len() {
+        diff.differences.push(Difference {
+            path: vec![PathSegment::Value(left_values.len().min(right_values.len()))],
+            kind: DiffKind::LengthMismatch(left_values.len(), right_values.len()),
+        });
+    }
+
+    for (i, (left_addr, right_addr)) in left_values.into_iter().zip(right_values).enumerate() {
+        diff_internal(left_addr, left, right_addr, right, vec![PathSegment::Value(i)], &names, &mut diff)?;
+    }
+
+    let left_registers = left.get_register_iter().filter_map(|i| left.get_register(i)).collect::<Vec<Data::Size>>();
+    let right_registers = right.get_register_iter().filter_map(|i| right.get_register(i)).collect::<Vec<Data::Size>>();
+    if left_registers.len() != right_registers.len() {
+        diff.differences.push(Difference {
+            path: vec![PathSegment::Register(left_registers.len().min(right_registers.len()))],
+            kind: DiffKind::LengthMismatch(left_registers.len(), right_registers.len()),
+        });
+    }
+
+    for (i, (left_addr, right_addr)) in left_registers.into_iter().zip(right_registers).enumerate() {
+        diff_internal(left_addr, left, right_addr, right, vec![PathSegment::Register(i)], &names, &mut diff)?;
+    }
+
+    Ok(diff)
+}
+
+fn diff_internal<Data: GarnishData>(
+    left_addr: Data::Size,
+    left: &Data,
+    right_addr: Data::Size,
+    right: &Data,
+    path: Vec<PathSegment>,
+    names: &dyn Fn(&Data::Symbol) -> Option<String>,
+    diff: &mut DataDiff,
+) -> Result<(), UtilityError<Data::Error>> {
+    traverse([((left_addr, right_addr), path)], &mut DiffTraversal { left, right, names, diff })
+}
+
+/// Compares pairs of values while they're traversed, with the path to each pair as context.
+/// Each pair of addresses is only compared once.
+struct DiffTraversal<'a, Data: GarnishData> {
+    left: &'a Data,
+    right: &'a Data,
+    names: &'a dyn Fn(&Data::Symbol) -> Option<String>,
+    diff: &'a mut DataDiff,
+}
+
+impl<'a, Data: GarnishData> DiffTraversal<'a, Data> {
+    fn key_segment(&self, key: &Data::Symbol) -> PathSegment {
+        match (self.names)(key) {
+            Some(name) => PathSegment::Key(name),
+            None => PathSegment::Symbol(key.to_string()),
+        }
+    }
+}
+
+/// Addresses of the left and right values being compared.
+type DiffNode<Size> = (Size, Size);
+
+impl<'a, Data: GarnishData> Traversal for DiffTraversal<'a, Data> {
+    type Node = DiffNode<Data::Size>;
+    type Context = Vec<PathSegment>;
+    type Leave = ();
+    type Error = UtilityError<Data::Error>;
+
+    fn enter(
+        &mut self,
+        (left_addr, right_addr): DiffNode<Data::Size>,
+        path: Vec<PathSegment>,
+    ) -> Result<Step<DiffNode<Data::Size>, Vec<PathSegment>, ()>, UtilityError<Data::Error>> {
+        let mut children = vec![];
+
+        let left_type = self.left.get_data_type(left_addr.clone())?;
+        let right_type = self.right.get_data_type(right_addr.clone())?;
+
+        if left_type != right_type {
+            self.diff.differences.push(Difference { path, kind: DiffKind::TypeMismatch(left_type, right_type) });
+            return Ok(Step::Leaf);
+        }
+
+        let mismatch = match left_type {
+            GarnishDataType::Invalid
+            | GarnishDataType::Custom
+            | GarnishDataType::Unit
+            | GarnishDataType::True
+            | GarnishDataType::False => None,
+            GarnishDataType::Number => compare(self.left.get_number(left_addr)?, self.right.get_number(right_addr)?),
+            GarnishDataType::Type => {
+                let (l, r) = (self.left.get_type(left_addr)?, self.right.get_type(right_addr)?);
+                (l != r).then(|| (format!("{:?}", l), format!("{:?}", r)))
+            }
+            GarnishDataType::Char => compare(self.left.get_char(left_addr)?, self.right.get_char(right_addr)?),
+            GarnishDataType::Byte => compare(self.left.get_byte(left_addr)?, self.right.get_byte(right_addr)?),
+            GarnishDataType::Symbol => compare(self.left.get_symbol(left_addr)?, self.right.get_symbol(right_addr)?),
+            GarnishDataType::Expression => compare(self.left.get_expression(left_addr)?, self.right.get_expression(right_addr)?),
+            GarnishDataType::External => compare(self.left.get_external(left_addr)?, self.right.get_external(right_addr)?),
+            GarnishDataType::CharList => {
+                let (l, r) = (read_char_list(left_addr, self.left)?, read_char_list(right_addr, self.right)?);
+                (l != r).then(|| (format!("\"{}\"", join(&l, "")), format!("\"{}\"", join(&r, ""))))
+            }
+            GarnishDataType::ByteList => {
+                let (l, r) = (read_byte_list(left_addr, self.left)?, read_byte_list(right_addr, self.right)?);
+                (l != r).then(|| (format!("'{}'", join(&l, " ")), format!("'{}'", join(&r, " "))))
+            }
+            GarnishDataType::Pair | GarnishDataType::Range | GarnishDataType::Slice => {
+                let ((l1, l2), (r1, r2), segments) = match left_type {
+                    GarnishDataType::Pair => (self.left.get_pair(left_addr)?, self.right.get_pair(right_addr)?, (PathSegment::Left, PathSegment::Right)),
+                    GarnishDataType::Range => (self.left.get_range(left_addr)?, self.right.get_range(right_addr)?, (PathSegment::Start, PathSegment::End)),
+                    _ => (self.left.get_slice(left_addr)?, self.right.get_slice(right_addr)?, (PathSegment::SliceValue, PathSegment::SliceRange)),
+                };
+
+                children.push(((l1, r1), extend(&path, segments.0)));
+                children.push(((l2, r2), extend(&path, segments.1)));
+                None
+            }
+            GarnishDataType::Concatenation => {
+                let mut l = vec![];
+                iterate_concatentation_checked(left_addr, self.left, CyclePolicy::Error, |item| l.push(item))?;
+                let mut r = vec![];
+                iterate_concatentation_checked(right_addr, self.right, CyclePolicy::Error, |item| r.push(item))?;
+
+                if l.len() != r.len() {
+                    self.diff.differences.push(Difference { path: path.clone(), kind: DiffKind::LengthMismatch(l.len(), r.len()) });
+                }
+
+                for (i, (l, r)) in l.into_iter().zip(r).enumerate() {
+                    children.push(((l, r), extend(&path, PathSegment::Index(i))));
+                }
+                None
+            }
+            GarnishDataType::List => {
+                let l = read_list(left_addr, self.left)?;
+                let r = read_list(right_addr, self.right)?;
+
+                if l.len() != r.len() {
+                    self.diff.differences.push(Difference { path: path.clone(), kind: DiffKind::LengthMismatch(l.len(), r.len()) });
+                }
+
+                for (i, ((l_item, l_key), (r_item, r_key))) in l.iter().zip(r.iter()).enumerate() {
+                    if l_key.is_none() || r_key.is_none() {
+                        children.push(((l_item.clone(), r_item.clone()), extend(&path, PathSegment::Index(i))));
+                    }
+                }
+
+                for (l_item, l_key) in l.iter() {
+                    if let Some(l_key) = l_key {
+                        let (_, l_value) = self.left.get_pair(l_item.clone())?;
+                        match find_key(&r, l_key, self.right)? {
+                            Some(r_value) => {
+                                children.push(((l_value, r_value), extend(&path, self.key_segment(l_key))))
+                            }
+                            None => self.diff.differences.push(Difference {
+                                path: extend(&path, self.key_segment(l_key)),
+                                kind: DiffKind::MissingKey(l_key.to_string(), DiffSide::Right),
+                            }),
+                        }
+                    }
+                }
+
+                for (_, r_key) in r.iter() {
+                    if let Some(r_key) = r_key {
+                        if find_key(&l, r_key, self.left)?.is_none() {
+                            self.diff.differences.push(Difference {
+                                path: extend(&path, self.key_segment(r_key)),
+                                kind: DiffKind::MissingKey(r_key.to_string(), DiffSide::Left),
+                            });
+                        }
+                    }
+                }
+
+                None
+            }
+        };
+
+        if let Some((l, r)) = mismatch {
+            self.diff.differences.push(Difference { path, kind: DiffKind::ValueMismatch(l, r) });
+        }
+
+        Ok(match children.is_empty() {
+            true => Step::Leaf,
+            false => Step::Children(children, ()),
+        })
+    }
+}
+
+fn compare<T: PartialEq + Display>(left: T, right: T) -> Option<(String, String)> {
+    (left != right).then(|| (left.to_string(), right.to_string()))
+}
+
+pub(crate) fn extend(path: &[PathSegment], segment: PathSegment) -> Vec<PathSegment> {
+    let mut path = path.to_vec();
+    path.push(segment);
+    path
+}
+
+pub(crate) fn join<T: Display>(items: &[T], separator: &str) -> String {
+    items.iter().map(|item| item.to_string()).collect::<Vec<String>>().join(separator)
+}
+
+pub(crate) fn read_char_list<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Vec<Data::Char>, Data::Error> {
+    let mut chars = vec![];
+    for i in data.get_char_list_iter(addr.clone()) {
+        chars.push(data.get_char_list_item(addr.clone(), i)?);
+    }
+    Ok(chars)
+}
+
+pub(crate) fn read_byte_list<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Vec<Data::Byte>, Data::Error> {
+    let mut bytes = vec![];
+    for i in data.get_byte_list_iter(addr.clone()) {
+        bytes.push(data.get_byte_list_item(addr.clone(), i)?);
+    }
+    Ok(bytes)
+}
+
+/// List item paired with its symbol if the item is an association.
+type KeyedItem<Data> = (<Data as GarnishData>::Size, Option<<Data as GarnishData>::Symbol>);
+
+fn read_list<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Vec<KeyedItem<Data>>, Data::Error> {
+    let mut items = vec![];
+    for i in data.get_list_items_iter(addr.clone()) {
+        let item = data.get_list_item(addr.clone(), i)?;
+        let key = match data.get_data_type(item.clone())? {
+            GarnishDataType::Pair => {
+                let (key, _) = data.get_pair(item.clone())?;
+                match data.get_data_type(key.clone())? {
+                    GarnishDataType::Symbol => Some(data.get_symbol(key)?),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        items.push((item, key));
+    }
+    Ok(items)
+}
+
+fn find_key<Data: GarnishData>(
+    items: &[KeyedItem<Data>],
+    key: &Data::Symbol,
+    data: &Data,
+) -> Result<Option<Data::Size>, Data::Error> {
+    for (item, item_key) in items.iter() {
+        if item_key.as_ref() == Some(key) {
+            return data.get_pair(item.clone()).map(|(_, value)| Some(value));
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleData, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, GarnishDataType};
+    use crate::data::{diff_data, diff_data_with_symbol_names, diff_stores, DiffKind, DiffSide, Difference, PathSegment};
+    use crate::UtilityError;
+
+    fn make_record(data: &mut SimpleGarnishData, value: i32, extra: Option<u64>) -> usize {
+        let k1 = data.add_symbol(1).unwrap();
+        let v1 = data.add_number(SimpleNumber::Integer(value)).unwrap();
+        let p1 = data.add_pair((k1, v1)).unwrap();
+        let item = data.parse_add_char_list("\"abc\"").unwrap();
+
+        let mut items = vec![(p1, true), (item, false)];
+        if let Some(sym) = extra {
+            let k2 = data.add_symbol(sym).unwrap();
+            let p2 = data.add_pair((k2, v1)).unwrap();
+            items.push((p2, true));
+        }
+
+        data.start_list(items.len()).unwrap();
+        for (item, is_association) in items {
+            data.add_to_list(item, is_association).unwrap();
+        }
+        data.end_list().unwrap()
+    }
+
+    #[test]
+    fn equal_values() {
+        let mut left = SimpleGarnishData::new();
+        let l = make_record(&mut left, 10, None);
+        let mut right = SimpleGarnishData::new();
+        right.add_number(SimpleNumber::Integer(500)).unwrap();
+        let r = make_record(&mut right, 10, None);
+
+        let diff = diff_data(l, &left, r, &right).unwrap();
+
+        assert!(diff.is_empty(), "{}", diff);
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let mut left = SimpleGarnishData::new();
+        let l = left.add_number(SimpleNumber::Integer(10)).unwrap();
+        let mut right = SimpleGarnishData::new();
+        let r = right.add_symbol(10).unwrap();
+
+        let diff = diff_data(l, &left, r, &right).unwrap();
+
+        assert_eq!(
+            diff.differences,
+            vec![Difference { path: vec![], kind: DiffKind::TypeMismatch(GarnishDataType::Number, GarnishDataType::Symbol) }]
+        );
+        assert_eq!(diff.to_string(), "$: type Number != Symbol\n");
+    }
+
+    #[test]
+    fn association_value_mismatch() {
+        let mut left = SimpleGarnishData::new();
+        let l = make_record(&mut left, 10, None);
+        let mut right = SimpleGarnishData::new();
+        let r = make_record(&mut right, 20, None);
+
+        let diff = diff_data(l, &left, r, &right).unwrap();
+
+        assert_eq!(
+            diff.differences,
+            vec![Difference { path: vec![PathSegment::Symbol("1".to_string())], kind: DiffKind::ValueMismatch("10".to_string(), "20".to_string()) }]
+        );
+        assert_eq!(diff.to_string(), "$.<sym 1>: 10 != 20\n");
+    }
+
+    #[test]
+    fn association_named_key() {
+        let mut left = SimpleGarnishData::new();
+        let l1 = left.parse_add_symbol("name").unwrap();
+        let l2 = left.add_number(SimpleNumber::Integer(10)).unwrap();
+        let l3 = left.add_pair((l1, l2)).unwrap();
+        left.start_list(1).unwrap();
+        left.add_to_list(l3, true).unwrap();
+        let l = left.end_list().unwrap();
+
+        let mut right = SimpleGarnishData::new();
+        let r1 = right.parse_add_symbol("name").unwrap();
+        let r2 = right.add_number(SimpleNumber::Integer(20)).unwrap();
+        let r3 = right.add_pair((r1, r2)).unwrap();
+        right.start_list(1).unwrap();
+        right.add_to_list(r3, true).unwrap();
+        let r = right.end_list().unwrap();
+
+        let diff = diff_data_with_symbol_names(l, &left, r, &right, |sym| left.get_symbols().get(sym).cloned()).unwrap();
+
+        assert_eq!(diff.differences[0].path, vec![PathSegment::Key("name".to_string())]);
+        assert_eq!(diff.to_string(), "$.name: 10 != 20\n");
+    }
+
+    #[test]
+    fn association_unnamed_key() {
+        let mut left = SimpleGarnishData::new();
+        let l = make_record(&mut left, 10, None);
+        let mut right = SimpleGarnishData::new();
+        let r = make_record(&mut right, 20, None);
+
+        let diff = diff_data_with_symbol_names(l, &left, r, &right, |sym| left.get_symbols().get(sym).cloned()).unwrap();
+
+        assert_eq!(diff.to_string(), "$.<sym 1>: 10 != 20\n");
+    }
+
+    #[test]
+    fn missing_key_and_length() {
+        let mut left = SimpleGarnishData::new();
+        let l = make_record(&mut left, 10, None);
+        let mut right = SimpleGarnishData::new();
+        let r = make_record(&mut right, 10, Some(2));
+
+        let diff = diff_data(l, &left, r, &right).unwrap();
+
+        assert_eq!(diff.to_string(), "$: length 2 != 3\n$.<sym 2>: missing from left\n");
+        assert_eq!(diff.differences[1].path, vec![PathSegment::Symbol("2".to_string())]);
+        assert_eq!(diff.differences[1].kind, DiffKind::MissingKey("2".to_string(), DiffSide::Left));
+    }
+
+    #[test]
+    fn nested_paths() {
+        let mut left = SimpleGarnishData::new();
+        let l1 = left.parse_add_char_list("\"abc\"").unwrap();
+        let l2 = left.add_number(SimpleNumber::Integer(1)).unwrap();
+        let l3 = left.add_pair((l1, l2)).unwrap();
+        let l4 = left.add_concatenation(l2, l3).unwrap();
+
+        let mut right = SimpleGarnishData::new();
+        let r1 = right.parse_add_char_list("\"abd\"").unwrap();
+        let r2 = right.add_number(SimpleNumber::Integer(1)).unwrap();
+        let r3 = right.add_pair((r1, r2)).unwrap();
+        let r4 = right.add_concatenation(r2, r3).unwrap();
+
+        let diff = diff_data(l4, &left, r4, &right).unwrap();
+
+        assert_eq!(diff.to_string(), "$[1].left: \"abc\" != \"abd\"\n");
+    }
+
+    #[test]
+    fn concatenation_cycle_is_error() {
+        let mut left = SimpleGarnishData::new();
+        let l1 = left.add_number(SimpleNumber::Integer(1)).unwrap();
+        let cat = left.get_data_len();
+        left.get_data_mut().push(SimpleData::Concatenation(l1, cat));
+
+        let result = diff_data(cat, &left, cat, &left);
+
+        assert!(matches!(result, Err(UtilityError::Cycle(_))));
+    }
+
+    #[test]
+    fn stores() {
+        let mut left = SimpleGarnishData::new();
+        let l1 = left.add_number(SimpleNumber::Integer(1)).unwrap();
+        let l2 = left.add_number(SimpleNumber::Integer(2)).unwrap();
+        left.push_value_stack(l1).unwrap();
+        left.push_value_stack(l2).unwrap();
+        left.push_register(l1).unwrap();
+
+        let mut right = SimpleGarnishData::new();
+        let r1 = right.add_number(SimpleNumber::Integer(1)).unwrap();
+        let r2 = right.add_number(SimpleNumber::Integer(3)).unwrap();
+        right.push_value_stack(r1).unwrap();
+        right.push_value_stack(r2).unwrap();
+        right.push_value_stack(r2).unwrap();
+        right.push_register(r2).unwrap();
+
+        let diff = diff_stores(&left, &right).unwrap();
+
+        assert_eq!(
+            diff.to_string(),
+            "$.values[2]: length 2 != 3\n$.values[1]: 2 != 3\n$.registers[0]: 1 != 3\n"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use garnish_lang_traits::{GarnishData, GarnishDataType, TypeConstants};
+use crate::address_set::AddressSet;
+use crate::data::copy::clone_value;
+use crate::data::{push_registers_and_jump_path, read_jump_path, registers};
+use crate::{AddressMap, UtilityError};
+
+/// Copies instructions, jump table, data, value stack, registers, jump path and instruction cursor of one runtime into a
+/// newly created one, keeping every data address the same so the copy can execute independently of the original.
+///
+/// Data slots are copied in order of their address with any references left as they are, so values may refer to slots
+/// after them or to themselves. Slots of jump path entries kept in registers are recreated by pushing the entry, and every
+/// register is then pushed with its original address.
+///
+/// Returns [`UtilityError::AddressMismatch`] if `to` places any slot at a different address, such as when it already holds
+/// data or `from` holds the same value in more than one slot and `to` reuses the first.
+/// `to` isn't rolled back on failure and keeps anything copied before the error, so a failed fork should be discarded.
+pub fn fork_data<Data: GarnishData + Clone>(from: &Data, to: &mut Data) -> Result<(), UtilityError<Data::Error>> {
+    let jump_path = read_jump_path(from);
+    let registers = registers(from);
+
+    // slots of jump path entries kept in registers, with the point of each entry
+    let mut frames = AddressMap::new();
+    let mut index = Data::Size::zero();
+    for addr in registers.iter() {
+        if let Some(entry) = jump_path.iter().find(|entry| entry.in_register && entry.register_len == index) {
+            frames.insert(addr.clone(), entry.point.clone());
+        }
+
+        index += Data::Size::one();
+    }
+
+    for addr in from.get_data_iter() {
+        let new_addr = match frames.get(&addr) {
+            Some(point) => {
+                // registers are pushed once all data is copied, so the entry's is the only register
+                to.push_jump_path(point.clone())?;
+                let new_addr = to.get_register_iter().next_back().and_then(|i| to.get_register(i));
+                to.pop_jump_path();
+
+                match new_addr {
+                    Some(new_addr) => new_addr,
+                    None => Err(UtilityError::AddressMismatch(format!(
+                        "Jump path entry at address {} wasn't kept in a register",
+                        addr
+                    )))?,
+                }
+            }
+            None => fork_value(addr.clone(), from, to)?,
+        };
+
+        if new_addr != addr {
+            Err(UtilityError::AddressMismatch(format!("Data at address {} was placed at address {}", addr, new_addr)))?;
+        }
+    }
+
+    for i in from.get_instruction_iter() {
+        if let Some((instruction, operand)) = from.get_instruction(i) {
+            to.push_instruction(instruction, operand)?;
+        }
+    }
+
+    for i in from.get_jump_table_iter() {
+        if let Some(point) = from.get_jump_point(i) {
+            to.push_jump_point(point)?;
+        }
+    }
+
+    for i in from.get_value_iter() {
+        if let Some(addr) = from.get_value(i) {
+            to.push_value_stack(addr)?;
+        }
+    }
+
+    // registers holding entries now point at the recreated slots, so only entries kept elsewhere are pushed again
+    let jump_path = jump_path.into_iter().filter(|entry| !entry.in_register).collect::<Vec<_>>();
+    push_registers_and_jump_path(&registers, &jump_path, to, |addr| addr)?;
+    to.set_instruction_cursor(from.get_instruction_cursor())?;
+
+    Ok(())
+}
+
+/// Copies a single data slot, keeping the addresses it refers to.
+fn fork_value<Data: GarnishData>(addr: Data::Size, from: &Data, to: &mut Data) -> Result<Data::Size, Data::Error> {
+    match from.get_data_type(addr.clone())? {
+        GarnishDataType::Pair => to.add_pair(from.get_pair(addr)?),
+        GarnishDataType::Range => {
+            let (start, end) = from.get_range(addr)?;
+            to.add_range(start, end)
+        }
+        GarnishDataType::Concatenation => {
+            let (left, right) = from.get_concatenation(addr)?;
+            to.add_concatenation(left, right)
+        }
+        GarnishDataType::Slice => {
+            let (list, range) = from.get_slice(addr)?;
+            to.add_slice(list, range)
+        }
+        GarnishDataType::List => {
+            let mut associations = AddressSet::new();
+            for i in from.get_list_associations_iter(addr.clone()) {
+                associations.insert(from.get_list_association(addr.clone(), i)?);
+            }
+
+            let mut items = vec![];
+            for i in from.get_list_items_iter(addr.clone()) {
+                items.push(from.get_list_item(addr.clone(), i)?);
+            }
+
+            to.start_list(from.get_list_len(addr)?)?;
+            for item in items {
+                let is_association = associations.contains(&item);
+                to.add_to_list(item, is_association)?;
+            }
+            to.end_list()
+        }
+        data_type => clone_value(addr, data_type, from, to, None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleData, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, Instruction};
+    use crate::data::{fork_data, read_jump_path};
+    use crate::UtilityError;
+
+    fn make_runtime() -> SimpleGarnishData {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_symbol(100).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d3 = data.add_pair((d1, d2)).unwrap();
+        let d4 = data.parse_add_char_list("\"abc\"").unwrap();
+        data.start_list(2).unwrap();
+        data.add_to_list(d3, true).unwrap();
+        data.add_to_list(d4, false).unwrap();
+        let list = data.end_list().unwrap();
+
+        data.push_instruction(Instruction::Put, Some(list)).unwrap();
+        data.push_instruction(Instruction::EndExpression, None).unwrap();
+        data.push_jump_point(0).unwrap();
+        data.push_value_stack(list).unwrap();
+        data.push_register(d2).unwrap();
+
+        data
+    }
+
+    #[test]
+    fn fork() {
+        let from = make_runtime();
+
+        let mut to = SimpleGarnishData::new();
+        fork_data(&from, &mut to).unwrap();
+
+        assert_eq!(to.get_data(), from.get_data());
+        assert_eq!(to.get_instructions(), from.get_instructions());
+        assert_eq!(to.get_jump_points(), from.get_jump_points());
+        assert_eq!(to.get_registers(), from.get_registers());
+        assert_eq!(to.get_value_stack_len(), 1);
+        assert_eq!(to.get_value(0), from.get_value(0));
+    }
+
+    #[test]
+    fn fork_is_independent() {
+        let from = make_runtime();
+
+        let mut to = SimpleGarnishData::new();
+        fork_data(&from, &mut to).unwrap();
+
+        let addr = to.add_number(SimpleNumber::Integer(500)).unwrap();
+        to.push_value_stack(addr).unwrap();
+
+        assert_eq!(to.get_value_stack_len(), 2);
+        assert_eq!(from.get_value_stack_len(), 1);
+        assert_eq!(to.get_data_len(), from.get_data_len() + 1);
+    }
+
+    #[test]
+    fn fork_into_used_store_is_error() {
+        let from = make_runtime();
+
+        let mut to = SimpleGarnishData::new();
+        to.add_number(SimpleNumber::Integer(500)).unwrap();
+
+        let result = fork_data(&from, &mut to);
+
+        assert!(matches!(result, Err(UtilityError::AddressMismatch(_))));
+    }
+
+    #[test]
+    fn fork_forward_reference_and_jump_path() {
+        let mut from = SimpleGarnishData::new();
+        let pair = from.get_data_len();
+        from.get_data_mut().push(SimpleData::Pair(pair + 1, pair + 2));
+        from.add_number(SimpleNumber::Integer(10)).unwrap();
+        from.add_number(SimpleNumber::Integer(20)).unwrap();
+        from.push_register(pair).unwrap();
+        from.push_jump_path(3).unwrap();
+        from.push_register(pair + 1).unwrap();
+        from.push_value_stack(pair).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        fork_data(&from, &mut to).unwrap();
+
+        assert_eq!(to.get_data(), from.get_data());
+        assert_eq!(to.get_registers(), from.get_registers());
+        assert_eq!(to.get_value(0), Some(pair));
+        assert_eq!(read_jump_path(&to), read_jump_path(&from));
+        assert_eq!(to.pop_jump_path(), Some(3));
+        assert_eq!(to.pop_register().unwrap(), Some(pair));
+    }
+
+    #[test]
+    fn fork_cycle() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let cat = from.get_data_len();
+        from.get_data_mut().push(SimpleData::Concatenation(d1, cat));
+        from.push_value_stack(cat).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        fork_data(&from, &mut to).unwrap();
+
+        assert_eq!(to.get_data(), from.get_data());
+        assert_eq!(to.get_value(0), Some(cat));
+    }
+}
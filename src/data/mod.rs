@@ -1,3 +1,13 @@
+mod collect;
 mod copy;
+mod diff;
+mod fork;
+mod report;
+mod snapshot;
 
-pub use copy::*;
\ No newline at end of file
+pub use collect::*;
+pub use copy::*;
+pub use diff::*;
+pub use fork::*;
+pub use report::*;
+pub use snapshot::*;
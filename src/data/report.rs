@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::address_set::AddressSet;
+use crate::data::{mark_reachable, root_addresses};
+
+/// Summary of how the data slots of a store are used, created by [`analyze_data`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataUsageReport<Size> {
+    /// Addresses reachable from the value stack, registers and instructions, in ascending order.
+    pub reachable: Vec<Size>,
+    /// Number of reachable values of each type.
+    pub type_counts: BTreeMap<GarnishDataType, usize>,
+    /// Combined length of all reachable char lists.
+    pub char_list_length: usize,
+    /// Combined length of all reachable byte lists.
+    pub byte_list_length: usize,
+    pub total_count: usize,
+    /// Number of data slots not reachable from any root, leaving out the Unit, True and False values every store holds.
+    pub unreachable_count: usize,
+    /// Roots that retain the most values, largest first, paired with that count.
+    /// Roots are counted in the order of [`root_addresses`], so a value reachable from more than one root only counts for the first.
+    pub largest_roots: Vec<(Size, usize)>,
+}
+
+/// Computes a [`DataUsageReport`] for the given store, listing up to `largest_count` of the largest retained values.
+pub fn analyze_data<Data: GarnishData + Clone>(
+    data: &Data,
+    largest_count: usize,
+) -> Result<DataUsageReport<Data::Size>, Data::Error> {
+    let roots = root_addresses(data);
+    let (marked, counts) = mark_reachable(&roots, data)?;
+
+    let mut type_counts = BTreeMap::new();
+    let mut char_list_length = 0;
+    let mut byte_list_length = 0;
+
+    for addr in marked.iter() {
+        let data_type = data.get_data_type(addr.clone())?;
+        *type_counts.entry(data_type).or_insert(0) += 1;
+
+        match data_type {
+            GarnishDataType::CharList => char_list_length += data.get_char_list_iter(addr.clone()).count(),
+            GarnishDataType::ByteList => byte_list_length += data.get_byte_list_iter(addr.clone()).count(),
+            _ => (),
+        }
+    }
+
+    let mut total_count = 0;
+    let mut unreachable_count = 0;
+    for addr in data.get_data_iter() {
+        total_count += 1;
+        if !marked.contains(&addr) && !is_constant(data.get_data_type(addr)?) {
+            unreachable_count += 1;
+        }
+    }
+
+    let mut seen_roots = AddressSet::new();
+    let mut largest_roots = vec![];
+    for (root, retained) in roots.into_iter().zip(counts) {
+        if seen_roots.insert(root.clone()) {
+            largest_roots.push((root, retained));
+        }
+    }
+
+    largest_roots.sort_by(|(_, a), (_, b)| b.cmp(a));
+    largest_roots.truncate(largest_count);
+
+    Ok(DataUsageReport {
+        reachable: marked.iter().cloned().collect(),
+        type_counts,
+        char_list_length,
+        byte_list_length,
+        total_count,
+        unreachable_count,
+        largest_roots,
+    })
+}
+
+/// Types of the values a store always holds, whether or not anything refers to them.
+fn is_constant(data_type: GarnishDataType) -> bool {
+    matches!(data_type, GarnishDataType::Unit | GarnishDataType::True | GarnishDataType::False)
+}
+
+impl<Size: Display> Display for DataUsageReport<Size> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Data slots: {}", self.total_count)?;
+        writeln!(f, "Reachable: {}", self.reachable.len())?;
+        writeln!(f, "Unreachable: {}", self.unreachable_count)?;
+        writeln!(f, "Char list length: {}", self.char_list_length)?;
+        writeln!(f, "Byte list length: {}", self.byte_list_length)?;
+
+        writeln!(f, "Types:")?;
+        for (data_type, count) in self.type_counts.iter() {
+            writeln!(f, "  {:?}: {}", data_type, count)?;
+        }
+
+        writeln!(f, "Largest roots:")?;
+        for (addr, count) in self.largest_roots.iter() {
+            writeln!(f, "  {}: {}", addr, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, GarnishDataType};
+    use crate::data::analyze_data;
+
+    #[test]
+    fn report() {
+        let mut data = SimpleGarnishData::new();
+        let _garbage = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d1 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d2 = data.parse_add_char_list("\"abc\"").unwrap();
+        let d3 = data.parse_add_byte_list("''1 2''").unwrap();
+        let d4 = data.add_pair((d1, d2)).unwrap();
+
+        data.start_list(2).unwrap();
+        data.add_to_list(d4, false).unwrap();
+        data.add_to_list(d3, false).unwrap();
+        let list = data.end_list().unwrap();
+
+        data.push_value_stack(list).unwrap();
+        data.push_register(d1).unwrap();
+
+        let report = analyze_data(&data, 1).unwrap();
+
+        assert_eq!(report.reachable, vec![d1, d2, d3, d4, list]);
+        assert_eq!(report.type_counts.get(&GarnishDataType::Number), Some(&1));
+        assert_eq!(report.type_counts.get(&GarnishDataType::CharList), Some(&1));
+        assert_eq!(report.type_counts.get(&GarnishDataType::ByteList), Some(&1));
+        assert_eq!(report.type_counts.get(&GarnishDataType::Pair), Some(&1));
+        assert_eq!(report.type_counts.get(&GarnishDataType::List), Some(&1));
+        assert_eq!(report.char_list_length, 3);
+        assert_eq!(report.byte_list_length, 2);
+        assert_eq!(report.total_count, 9);
+        assert_eq!(report.unreachable_count, 1);
+        assert_eq!(report.largest_roots, vec![(list, 5)]);
+    }
+
+    #[test]
+    fn shared_values_count_for_first_root() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d3 = data.add_pair((d1, d2)).unwrap();
+        let d4 = data.add_pair((d3, d1)).unwrap();
+
+        data.push_value_stack(d3).unwrap();
+        data.push_value_stack(d4).unwrap();
+
+        let report = analyze_data(&data, 5).unwrap();
+
+        assert_eq!(report.largest_roots, vec![(d3, 3), (d4, 1)]);
+        assert_eq!(report.unreachable_count, 0);
+    }
+
+    #[test]
+    fn render() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        data.push_value_stack(d1).unwrap();
+
+        let report = analyze_data(&data, 5).unwrap();
+
+        assert_eq!(
+            report.to_string(),
+            "Data slots: 4\nReachable: 1\nUnreachable: 0\nChar list length: 0\nByte list length: 0\nTypes:\n  Number: 1\nLargest roots:\n  3: 1\n"
+        );
+    }
+}
@@ -0,0 +1,458 @@
+use std::fmt::{Debug, Formatter};
+use garnish_lang_traits::{GarnishData, GarnishDataType, Instruction};
+use crate::data::{add_list, instruction_has_data_operand, push_registers_and_jump_path, read_jump_path, registers, JumpPathEntry};
+use crate::visitor::{traverse, Step, Traversal};
+use crate::{AddressMap, UtilityError, VisitAction};
+
+/// Copy of a single data slot, with any references kept as addresses of the snapshotted store.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotValue<Size, Number, Char, Byte, Symbol> {
+    /// Invalid and Custom values can't be read through [`GarnishData`] and are restored as Unit.
+    Unsupported(GarnishDataType),
+    Unit,
+    True,
+    False,
+    Number(Number),
+    Type(GarnishDataType),
+    Char(Char),
+    CharList(Vec<Char>),
+    Byte(Byte),
+    ByteList(Vec<Byte>),
+    Symbol(Symbol),
+    Expression(Size),
+    External(Size),
+    Pair(Size, Size),
+    Range(Size, Size),
+    Concatenation(Size, Size),
+    Slice(Size, Size),
+    List(Vec<Size>),
+}
+
+pub type DataSnapshotValue<Data> = SnapshotValue<
+    <Data as GarnishData>::Size,
+    <Data as GarnishData>::Number,
+    <Data as GarnishData>::Char,
+    <Data as GarnishData>::Byte,
+    <Data as GarnishData>::Symbol,
+>;
+
+/// Runtime state captured by [`take_snapshot`].
+pub struct DataSnapshot<Data: GarnishData> {
+    /// Every data slot paired with its address.
+    pub data: Vec<(Data::Size, DataSnapshotValue<Data>)>,
+    pub values: Vec<Data::Size>,
+    /// Registers in the order they were pushed, including any holding a jump path entry.
+    pub registers: Vec<Data::Size>,
+    pub instructions: Vec<(Instruction, Option<Data::Size>)>,
+    pub jump_table: Vec<Data::Size>,
+    pub jump_path: Vec<JumpPathEntry<Data::Size>>,
+    pub instruction_cursor: Data::Size,
+}
+
+impl<Data: GarnishData> Clone for DataSnapshot<Data> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            values: self.values.clone(),
+            registers: self.registers.clone(),
+            instructions: self.instructions.clone(),
+            jump_table: self.jump_table.clone(),
+            jump_path: self.jump_path.clone(),
+            instruction_cursor: self.instruction_cursor.clone(),
+        }
+    }
+}
+
+impl<Data: GarnishData> PartialEq for DataSnapshot<Data> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+            && self.values == other.values
+            && self.registers == other.registers
+            && self.instructions == other.instructions
+            && self.jump_table == other.jump_table
+            && self.jump_path == other.jump_path
+            && self.instruction_cursor == other.instruction_cursor
+    }
+}
+
+impl<Data: GarnishData> Debug for DataSnapshot<Data> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DataSnapshot")
+            .field("data", &self.data)
+            .field("values", &self.values)
+            .field("registers", &self.registers)
+            .field("instructions", &self.instructions)
+            .field("jump_table", &self.jump_table)
+            .field("jump_path", &self.jump_path)
+            .field("instruction_cursor", &self.instruction_cursor)
+            .finish()
+    }
+}
+
+/// Captures the state of a runtime. The jump path is read with [`read_jump_path`], which pops it from a clone of the runtime.
+pub fn take_snapshot<Data: GarnishData + Clone>(data: &Data) -> Result<DataSnapshot<Data>, Data::Error> {
+    let mut snapshot = take_snapshot_without_jump_path(data)?;
+    snapshot.jump_path = read_jump_path(data);
+
+    Ok(snapshot)
+}
+
+/// Snapshot for implementations that can't be cloned to read their jump path. Jump path is left empty, so any registers
+/// holding jump path entries are restored as data.
+pub fn take_snapshot_without_jump_path<Data: GarnishData>(data: &Data) -> Result<DataSnapshot<Data>, Data::Error> {
+    let mut slots = vec![];
+    for addr in data.get_data_iter() {
+        let value = snapshot_value(addr.clone(), data)?;
+        slots.push((addr, value));
+    }
+
+    Ok(DataSnapshot {
+        data: slots,
+        values: data.get_value_iter().filter_map(|i| data.get_value(i)).collect(),
+        registers: registers(data),
+        instructions: data.get_instruction_iter().filter_map(|i| data.get_instruction(i)).collect(),
+        jump_table: data.get_jump_table_iter().filter_map(|i| data.get_jump_point(i)).collect(),
+        jump_path: vec![],
+        instruction_cursor: data.get_instruction_cursor(),
+    })
+}
+
+fn snapshot_value<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<DataSnapshotValue<Data>, Data::Error> {
+    Ok(match data.get_data_type(addr.clone())? {
+        t @ (GarnishDataType::Invalid | GarnishDataType::Custom) => SnapshotValue::Unsupported(t),
+        GarnishDataType::Unit => SnapshotValue::Unit,
+        GarnishDataType::True => SnapshotValue::True,
+        GarnishDataType::False => SnapshotValue::False,
+        GarnishDataType::Number => SnapshotValue::Number(data.get_number(addr)?),
+        GarnishDataType::Type => SnapshotValue::Type(data.get_type(addr)?),
+        GarnishDataType::Char => SnapshotValue::Char(data.get_char(addr)?),
+        GarnishDataType::CharList => {
+            let mut chars = vec![];
+            for i in data.get_char_list_iter(addr.clone()) {
+                chars.push(data.get_char_list_item(addr.clone(), i)?);
+            }
+            SnapshotValue::CharList(chars)
+        }
+        GarnishDataType::Byte => SnapshotValue::Byte(data.get_byte(addr)?),
+        GarnishDataType::ByteList => {
+            let mut bytes = vec![];
+            for i in data.get_byte_list_iter(addr.clone()) {
+                bytes.push(data.get_byte_list_item(addr.clone(), i)?);
+            }
+            SnapshotValue::ByteList(bytes)
+        }
+        GarnishDataType::Symbol => SnapshotValue::Symbol(data.get_symbol(addr)?),
+        GarnishDataType::Expression => SnapshotValue::Expression(data.get_expression(addr)?),
+        GarnishDataType::External => SnapshotValue::External(data.get_external(addr)?),
+        GarnishDataType::Pair => data.get_pair(addr).map(|(left, right)| SnapshotValue::Pair(left, right))?,
+        GarnishDataType::Range => data.get_range(addr).map(|(start, end)| SnapshotValue::Range(start, end))?,
+        GarnishDataType::Concatenation => data
+            .get_concatenation(addr)
+            .map(|(left, right)| SnapshotValue::Concatenation(left, right))?,
+        GarnishDataType::Slice => data.get_slice(addr).map(|(list, range)| SnapshotValue::Slice(list, range))?,
+        GarnishDataType::List => {
+            let mut items = vec![];
+            for i in data.get_list_items_iter(addr.clone()) {
+                items.push(data.get_list_item(addr.clone(), i)?);
+            }
+            SnapshotValue::List(items)
+        }
+    })
+}
+
+impl<Size: Clone, Number, Char, Byte, Symbol> SnapshotValue<Size, Number, Char, Byte, Symbol> {
+    fn references(&self) -> Vec<Size> {
+        match self {
+            SnapshotValue::Pair(left, right)
+            | SnapshotValue::Range(left, right)
+            | SnapshotValue::Concatenation(left, right)
+            | SnapshotValue::Slice(left, right) => vec![left.clone(), right.clone()],
+            SnapshotValue::List(items) => items.clone(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Rebuilds a snapshot into an empty store.
+///
+/// Data slots are added in order, with any slot referred to by another added first.
+/// Since the store may place data at different addresses, value stack, registers and data operands of instructions are
+/// updated to the new addresses before being pushed. Jump path entries are pushed between registers in their original order.
+///
+/// Returns map of snapshot addresses to their addresses in the restored store.
+pub fn restore_snapshot<Data: GarnishData>(
+    snapshot: &DataSnapshot<Data>,
+    to: &mut Data,
+) -> Result<AddressMap<Data::Size>, UtilityError<Data::Error>> {
+    let mut slots = AddressMap::new();
+    for (index, (addr, _)) in snapshot.data.iter().enumerate() {
+        slots.insert(addr.clone(), index);
+    }
+
+    let mut traversal = RestoreTraversal { snapshot, slots, to: &mut *to, map: AddressMap::new() };
+    traverse(snapshot.data.iter().map(|(addr, _)| (addr.clone(), ())), &mut traversal)?;
+    let map = traversal.map;
+
+    for (instruction, operand) in snapshot.instructions.iter() {
+        let operand = match operand {
+            Some(addr) if instruction_has_data_operand(*instruction) => Some(map.get(addr).cloned().unwrap_or(addr.clone())),
+            operand => operand.clone(),
+        };
+
+        to.push_instruction(*instruction, operand)?;
+    }
+
+    for point in snapshot.jump_table.iter() {
+        to.push_jump_point(point.clone())?;
+    }
+
+    for addr in snapshot.values.iter() {
+        to.push_value_stack(map.get(addr).cloned().unwrap_or(addr.clone()))?;
+    }
+
+    push_registers_and_jump_path(&snapshot.registers, &snapshot.jump_path, to, |addr| map.get(&addr).cloned().unwrap_or(addr))?;
+
+    to.set_instruction_cursor(snapshot.instruction_cursor.clone())?;
+
+    Ok(map)
+}
+
+/// Restores snapshot slots while they're traversed, each once the slots it refers to have been.
+struct RestoreTraversal<'a, Data: GarnishData> {
+    snapshot: &'a DataSnapshot<Data>,
+    /// Index into the snapshot's data for each snapshot address.
+    slots: AddressMap<Data::Size, usize>,
+    to: &'a mut Data,
+    map: AddressMap<Data::Size>,
+}
+
+impl<'a, Data: GarnishData> RestoreTraversal<'a, Data> {
+    fn value(&self, addr: &Data::Size) -> Result<&'a DataSnapshotValue<Data>, UtilityError<Data::Error>> {
+        match self.slots.get(addr) {
+            Some(index) => Ok(&self.snapshot.data[*index].1),
+            None => Err(UtilityError::InvalidSnapshot(format!("No data slot at address {}", addr))),
+        }
+    }
+
+    fn restore(&mut self, addr: Data::Size) -> Result<(), UtilityError<Data::Error>> {
+        let new_addr = restore_value(self.value(&addr)?, &self.map, self.to)?;
+        self.map.insert(addr, new_addr);
+        Ok(())
+    }
+}
+
+impl<'a, Data: GarnishData> Traversal for RestoreTraversal<'a, Data> {
+    type Node = Data::Size;
+    type Context = ();
+    type Leave = ();
+    type Error = UtilityError<Data::Error>;
+
+    fn enter(&mut self, addr: Data::Size, _context: ()) -> Result<Step<Data::Size, (), ()>, UtilityError<Data::Error>> {
+        if self.map.contains(&addr) {
+            return Ok(Step::Leaf);
+        }
+
+        let references = self.value(&addr)?.references();
+        if references.is_empty() {
+            self.restore(addr)?;
+            return Ok(Step::Leaf);
+        }
+
+        Ok(Step::Children(references.into_iter().map(|reference| (reference, ())).collect(), ()))
+    }
+
+    fn leave(&mut self, addr: Data::Size, _leave: ()) -> Result<VisitAction, UtilityError<Data::Error>> {
+        self.restore(addr)?;
+        Ok(VisitAction::Continue)
+    }
+
+    fn cycle(&mut self, addr: &Data::Size, _context: &()) -> Result<VisitAction, UtilityError<Data::Error>> {
+        Err(UtilityError::InvalidSnapshot(format!("Data slot at address {} refers to itself", addr)))
+    }
+}
+
+fn restore_value<Data: GarnishData>(
+    value: &DataSnapshotValue<Data>,
+    map: &AddressMap<Data::Size>,
+    to: &mut Data,
+) -> Result<Data::Size, Data::Error> {
+    let get = |addr: &Data::Size| map.get(addr).cloned().unwrap_or(addr.clone());
+
+    match value {
+        SnapshotValue::Unsupported(_) | SnapshotValue::Unit => to.add_unit(),
+        SnapshotValue::True => to.add_true(),
+        SnapshotValue::False => to.add_false(),
+        SnapshotValue::Number(v) => to.add_number(v.clone()),
+        SnapshotValue::Type(v) => to.add_type(*v),
+        SnapshotValue::Char(v) => to.add_char(v.clone()),
+        SnapshotValue::CharList(chars) => {
+            to.start_char_list()?;
+            for c in chars {
+                to.add_to_char_list(c.clone())?;
+            }
+            to.end_char_list()
+        }
+        SnapshotValue::Byte(v) => to.add_byte(v.clone()),
+        SnapshotValue::ByteList(bytes) => {
+            to.start_byte_list()?;
+            for b in bytes {
+                to.add_to_byte_list(b.clone())?;
+            }
+            to.end_byte_list()
+        }
+        SnapshotValue::Symbol(v) => to.add_symbol(v.clone()),
+        SnapshotValue::Expression(v) => to.add_expression(v.clone()),
+        SnapshotValue::External(v) => to.add_external(v.clone()),
+        SnapshotValue::Pair(left, right) => to.add_pair((get(left), get(right))),
+        SnapshotValue::Range(start, end) => to.add_range(get(start), get(end)),
+        SnapshotValue::Concatenation(left, right) => to.add_concatenation(get(left), get(right)),
+        SnapshotValue::Slice(list, range) => to.add_slice(get(list), get(range)),
+        SnapshotValue::List(items) => add_list(items.iter().map(get).collect(), to),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleData, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, Instruction};
+    use crate::data::{read_jump_path, restore_snapshot, take_snapshot, take_snapshot_without_jump_path, JumpPathEntry, SnapshotValue};
+    use crate::UtilityError;
+
+    #[test]
+    fn snapshot_and_restore() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_symbol(100).unwrap();
+        let d2 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d3 = from.add_pair((d1, d2)).unwrap();
+        let d4 = from.parse_add_char_list("\"abc\"").unwrap();
+        from.start_list(2).unwrap();
+        from.add_to_list(d3, true).unwrap();
+        from.add_to_list(d4, false).unwrap();
+        let list = from.end_list().unwrap();
+
+        from.push_instruction(Instruction::Put, Some(d2)).unwrap();
+        from.push_instruction(Instruction::JumpTo, Some(0)).unwrap();
+        from.push_jump_point(1).unwrap();
+        from.push_value_stack(list).unwrap();
+        from.push_register(d4).unwrap();
+        from.set_instruction_cursor(1).unwrap();
+
+        let snapshot = take_snapshot_without_jump_path(&from).unwrap();
+
+        assert_eq!(snapshot.data.len(), 8);
+        assert_eq!(snapshot.data[3], (d1, SnapshotValue::Symbol(100)));
+        assert_eq!(snapshot.data[7], (list, SnapshotValue::List(vec![d3, d4])));
+
+        let mut to = SimpleGarnishData::new();
+        let map = restore_snapshot(&snapshot, &mut to).unwrap();
+
+        assert_eq!(map.len(), 8);
+        assert_eq!(to.get_data(), from.get_data());
+        assert_eq!(to.get_value(0), Some(list));
+        assert_eq!(to.get_register(0), Some(d4));
+        assert_eq!(to.get_instruction(0), Some((Instruction::Put, Some(d2))));
+        assert_eq!(to.get_instruction(1), Some((Instruction::JumpTo, Some(0))));
+        assert_eq!(to.get_jump_point(0), Some(1));
+        assert_eq!(to.get_instruction_cursor(), 1);
+    }
+
+    #[test]
+    fn restore_relocates_addresses() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = from.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d3 = from.add_pair((d1, d2)).unwrap();
+        from.push_value_stack(d3).unwrap();
+        from.push_instruction(Instruction::Put, Some(d1)).unwrap();
+
+        let snapshot = take_snapshot_without_jump_path(&from).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        to.add_number(SimpleNumber::Integer(100)).unwrap();
+        let map = restore_snapshot(&snapshot, &mut to).unwrap();
+
+        assert_eq!(map.get(&d3), Some(&6));
+        assert_eq!(to.get_data().get(6).unwrap().as_pair().unwrap(), (4, 5));
+        assert_eq!(to.get_value(0), Some(6));
+        assert_eq!(to.get_instruction(0), Some((Instruction::Put, Some(4))));
+    }
+
+    #[test]
+    fn restore_forward_reference() {
+        let mut from = SimpleGarnishData::new();
+        let pair = from.get_data_len();
+        from.get_data_mut().push(SimpleData::Pair(pair + 1, pair + 2));
+        from.add_number(SimpleNumber::Integer(10)).unwrap();
+        from.add_number(SimpleNumber::Integer(20)).unwrap();
+
+        let snapshot = take_snapshot_without_jump_path(&from).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let map = restore_snapshot(&snapshot, &mut to).unwrap();
+
+        let new_pair = *map.get(&pair).unwrap();
+        let (left, right) = to.get_pair(new_pair).unwrap();
+        assert_eq!(to.get_number(left).unwrap(), SimpleNumber::Integer(10));
+        assert_eq!(to.get_number(right).unwrap(), SimpleNumber::Integer(20));
+    }
+
+    #[test]
+    fn restore_cycle_is_error() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let cat = from.get_data_len();
+        from.get_data_mut().push(SimpleData::Concatenation(d1, cat));
+
+        let snapshot = take_snapshot_without_jump_path(&from).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let result = restore_snapshot(&snapshot, &mut to);
+
+        assert!(matches!(result, Err(UtilityError::InvalidSnapshot(_))));
+    }
+
+    #[test]
+    fn restore_missing_slot_is_error() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        from.add_pair((d1, 100)).unwrap();
+
+        let snapshot = take_snapshot_without_jump_path(&from).unwrap();
+
+        let mut to = SimpleGarnishData::new();
+        let result = restore_snapshot(&snapshot, &mut to);
+
+        assert!(matches!(result, Err(UtilityError::InvalidSnapshot(_))));
+    }
+
+    #[test]
+    fn snapshot_and_restore_jump_path() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = from.add_number(SimpleNumber::Integer(20)).unwrap();
+        from.push_register(d1).unwrap();
+        from.push_jump_path(4).unwrap();
+        from.push_register(d2).unwrap();
+        from.push_jump_path(7).unwrap();
+
+        let snapshot = take_snapshot(&from).unwrap();
+
+        assert_eq!(
+            snapshot.jump_path,
+            vec![
+                JumpPathEntry { point: 4, register_len: 1, in_register: true },
+                JumpPathEntry { point: 7, register_len: 3, in_register: true }
+            ]
+        );
+
+        let mut to = SimpleGarnishData::new();
+        let map = restore_snapshot(&snapshot, &mut to).unwrap();
+
+        assert_eq!(read_jump_path(&to), snapshot.jump_path);
+        assert_eq!(to.get_register_len(), 4);
+        assert_eq!(to.pop_jump_path(), Some(7));
+        assert_eq!(to.pop_register().unwrap(), map.get(&d2).cloned());
+        assert_eq!(to.pop_jump_path(), Some(4));
+        assert_eq!(to.pop_register().unwrap(), map.get(&d1).cloned());
+    }
+}
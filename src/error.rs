@@ -0,0 +1,59 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Error for utilities that can fail for reasons other than an error from the [`garnish_lang_traits::GarnishData`] implementation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UtilityError<Source: 'static + Error> {
+    /// Error returned by the data implementation.
+    Data(Source),
+    /// Snapshot refers to a data slot it doesn't contain, or its data slots refer to each other in a cycle.
+    InvalidSnapshot(String),
+    /// Data was placed at a different address than expected when copying between stores.
+    AddressMismatch(String),
+    /// Value refers back to itself, which the utility can't handle.
+    Cycle(String),
+    /// Value isn't one of the types the utility accepts.
+    UnsupportedType(String),
+    /// Same key found in values being combined.
+    KeyConflict(String),
+    /// Character can't be converted between the data implementation and Rust.
+    InvalidChar(String),
+    /// Byte can't be converted between the data implementation and Rust.
+    InvalidByte(String),
+    /// Range can't be stepped through to its end.
+    InvalidRange(String),
+    /// Value is larger than the limit given to the utility.
+    LimitExceeded(String),
+}
+
+impl<Source: 'static + Error> Display for UtilityError<Source> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UtilityError::Data(source) => write!(f, "{}", source),
+            UtilityError::InvalidSnapshot(message) => write!(f, "Invalid snapshot: {}", message),
+            UtilityError::AddressMismatch(message) => write!(f, "Address mismatch: {}", message),
+            UtilityError::Cycle(message) => write!(f, "Cycle: {}", message),
+            UtilityError::UnsupportedType(message) => write!(f, "Unsupported type: {}", message),
+            UtilityError::KeyConflict(message) => write!(f, "Key conflict: {}", message),
+            UtilityError::InvalidChar(message) => write!(f, "Invalid char: {}", message),
+            UtilityError::InvalidByte(message) => write!(f, "Invalid byte: {}", message),
+            UtilityError::InvalidRange(message) => write!(f, "Invalid range: {}", message),
+            UtilityError::LimitExceeded(message) => write!(f, "Limit exceeded: {}", message),
+        }
+    }
+}
+
+impl<Source: 'static + Error> Error for UtilityError<Source> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UtilityError::Data(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl<Source: 'static + Error> From<Source> for UtilityError<Source> {
+    fn from(source: Source) -> Self {
+        UtilityError::Data(source)
+    }
+}
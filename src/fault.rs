@@ -0,0 +1,660 @@
+//! [`GarnishData`] wrapper that fails selected calls, for testing how code handles errors from the data implementation.
+//!
+//! Available with the `mock` feature.
+
+use std::cell::{Cell, RefCell};
+use garnish_lang_traits::{GarnishData, GarnishDataType, Instruction};
+
+/// Function deciding if a call fails, given the method name and the index of the call among all fallible calls.
+pub type Fault<Data> = Box<dyn Fn(&str, usize) -> Option<<Data as GarnishData>::Error>>;
+
+/// Wraps a [`GarnishData`] implementation, returning a configured error instead of calling the inner data for selected calls.
+///
+/// Only methods that return a [`Result`] can fail and only those are counted.
+///
+/// [`crate::mock::MockData`] can also fail calls, but its scripts are keyed on a method name and how many times that method was called.
+/// Faults here are keyed on the index of the call among all fallible calls, so a test can fail each step of an operation in turn without knowing which methods it makes.
+/// Calls aren't recorded either, which keeps long runs like fuzzing or generated data cheap.
+pub struct FaultyData<Inner: GarnishData> {
+    inner: Inner,
+    call_count: Cell<usize>,
+    fault_count: Cell<usize>,
+    faults: Vec<Fault<Inner>>,
+}
+
+impl<Inner: GarnishData> FaultyData<Inner> {
+    pub fn new(inner: Inner) -> Self {
+        Self { inner, call_count: Cell::new(0), fault_count: Cell::new(0), faults: vec![] }
+    }
+
+    pub fn inner(&self) -> &Inner {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut Inner {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> Inner {
+        self.inner
+    }
+
+    /// Number of fallible calls made so far.
+    pub fn call_count(&self) -> usize {
+        self.call_count.get()
+    }
+
+    /// Number of calls that were failed.
+    pub fn fault_count(&self) -> usize {
+        self.fault_count.get()
+    }
+
+    /// Fails the nth fallible call, starting at 0.
+    pub fn fail_nth_call(&mut self, n: usize, error: Inner::Error)
+    where
+        Inner: 'static,
+    {
+        let error = RefCell::new(Some(error));
+        self.faults.push(Box::new(move |_, index| match index == n {
+            true => error.borrow_mut().take(),
+            false => None,
+        }));
+    }
+
+    /// Fails every call the predicate returns true for, with an error created by `error`.
+    pub fn fail_when<P, E>(&mut self, predicate: P, error: E)
+    where
+        P: Fn(&str, usize) -> bool + 'static,
+        E: Fn() -> Inner::Error + 'static,
+    {
+        self.faults.push(Box::new(move |method, index| predicate(method, index).then(&error)));
+    }
+
+    /// Fails every call of the given method.
+    pub fn fail_method<E>(&mut self, method: &'static str, error: E)
+    where
+        E: Fn() -> Inner::Error + 'static,
+    {
+        self.fail_when(move |name, _| name == method, error);
+    }
+
+    fn check(&self, method: &str) -> Result<(), Inner::Error> {
+        let index = self.call_count.get();
+        self.call_count.set(index + 1);
+
+        match self.faults.iter().find_map(|fault| fault(method, index)) {
+            Some(error) => {
+                self.fault_count.set(self.fault_count.get() + 1);
+                Err(error)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<Inner: GarnishData> GarnishData for FaultyData<Inner> {
+    type Error = Inner::Error;
+    type Symbol = Inner::Symbol;
+    type Byte = Inner::Byte;
+    type Char = Inner::Char;
+    type Number = Inner::Number;
+    type Size = Inner::Size;
+    type SizeIterator = Inner::SizeIterator;
+    type NumberIterator = Inner::NumberIterator;
+    type InstructionIterator = Inner::InstructionIterator;
+    type DataIndexIterator = Inner::DataIndexIterator;
+    type ValueIndexIterator = Inner::ValueIndexIterator;
+    type RegisterIndexIterator = Inner::RegisterIndexIterator;
+    type JumpTableIndexIterator = Inner::JumpTableIndexIterator;
+    type JumpPathIndexIterator = Inner::JumpPathIndexIterator;
+    type ListIndexIterator = Inner::ListIndexIterator;
+
+    fn get_data_len(&self) -> Self::Size {
+        self.inner.get_data_len()
+    }
+
+    fn get_data_iter(&self) -> Self::DataIndexIterator {
+        self.inner.get_data_iter()
+    }
+
+    fn get_value_stack_len(&self) -> Self::Size {
+        self.inner.get_value_stack_len()
+    }
+
+    fn push_value_stack(&mut self, addr: Self::Size) -> Result<(), Self::Error> {
+        self.check("push_value_stack")?;
+        self.inner.push_value_stack(addr)
+    }
+
+    fn pop_value_stack(&mut self) -> Option<Self::Size> {
+        self.inner.pop_value_stack()
+    }
+
+    fn get_value(&self, addr: Self::Size) -> Option<Self::Size> {
+        self.inner.get_value(addr)
+    }
+
+    fn get_value_mut(&mut self, addr: Self::Size) -> Option<&mut Self::Size> {
+        self.inner.get_value_mut(addr)
+    }
+
+    fn get_current_value(&self) -> Option<Self::Size> {
+        self.inner.get_current_value()
+    }
+
+    fn get_current_value_mut(&mut self) -> Option<&mut Self::Size> {
+        self.inner.get_current_value_mut()
+    }
+
+    fn get_value_iter(&self) -> Self::ValueIndexIterator {
+        self.inner.get_value_iter()
+    }
+
+    fn get_data_type(&self, addr: Self::Size) -> Result<GarnishDataType, Self::Error> {
+        self.check("get_data_type")?;
+        self.inner.get_data_type(addr)
+    }
+
+    fn get_number(&self, addr: Self::Size) -> Result<Self::Number, Self::Error> {
+        self.check("get_number")?;
+        self.inner.get_number(addr)
+    }
+
+    fn get_type(&self, addr: Self::Size) -> Result<GarnishDataType, Self::Error> {
+        self.check("get_type")?;
+        self.inner.get_type(addr)
+    }
+
+    fn get_char(&self, addr: Self::Size) -> Result<Self::Char, Self::Error> {
+        self.check("get_char")?;
+        self.inner.get_char(addr)
+    }
+
+    fn get_byte(&self, addr: Self::Size) -> Result<Self::Byte, Self::Error> {
+        self.check("get_byte")?;
+        self.inner.get_byte(addr)
+    }
+
+    fn get_symbol(&self, addr: Self::Size) -> Result<Self::Symbol, Self::Error> {
+        self.check("get_symbol")?;
+        self.inner.get_symbol(addr)
+    }
+
+    fn get_expression(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("get_expression")?;
+        self.inner.get_expression(addr)
+    }
+
+    fn get_external(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("get_external")?;
+        self.inner.get_external(addr)
+    }
+
+    fn get_pair(&self, addr: Self::Size) -> Result<(Self::Size, Self::Size), Self::Error> {
+        self.check("get_pair")?;
+        self.inner.get_pair(addr)
+    }
+
+    fn get_concatenation(&self, addr: Self::Size) -> Result<(Self::Size, Self::Size), Self::Error> {
+        self.check("get_concatenation")?;
+        self.inner.get_concatenation(addr)
+    }
+
+    fn get_range(&self, addr: Self::Size) -> Result<(Self::Size, Self::Size), Self::Error> {
+        self.check("get_range")?;
+        self.inner.get_range(addr)
+    }
+
+    fn get_slice(&self, addr: Self::Size) -> Result<(Self::Size, Self::Size), Self::Error> {
+        self.check("get_slice")?;
+        self.inner.get_slice(addr)
+    }
+
+    fn get_list_len(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("get_list_len")?;
+        self.inner.get_list_len(addr)
+    }
+
+    fn get_list_item(&self, list_addr: Self::Size, item_addr: Self::Number) -> Result<Self::Size, Self::Error> {
+        self.check("get_list_item")?;
+        self.inner.get_list_item(list_addr, item_addr)
+    }
+
+    fn get_list_associations_len(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("get_list_associations_len")?;
+        self.inner.get_list_associations_len(addr)
+    }
+
+    fn get_list_association(&self, list_addr: Self::Size, item_addr: Self::Number) -> Result<Self::Size, Self::Error> {
+        self.check("get_list_association")?;
+        self.inner.get_list_association(list_addr, item_addr)
+    }
+
+    fn get_list_item_with_symbol(&self, list_addr: Self::Size, sym: Self::Symbol) -> Result<Option<Self::Size>, Self::Error> {
+        self.check("get_list_item_with_symbol")?;
+        self.inner.get_list_item_with_symbol(list_addr, sym)
+    }
+
+    fn get_list_items_iter(&self, list_addr: Self::Size) -> Self::ListIndexIterator {
+        self.inner.get_list_items_iter(list_addr)
+    }
+
+    fn get_list_associations_iter(&self, list_addr: Self::Size) -> Self::ListIndexIterator {
+        self.inner.get_list_associations_iter(list_addr)
+    }
+
+    fn get_char_list_len(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("get_char_list_len")?;
+        self.inner.get_char_list_len(addr)
+    }
+
+    fn get_char_list_item(&self, addr: Self::Size, item_index: Self::Number) -> Result<Self::Char, Self::Error> {
+        self.check("get_char_list_item")?;
+        self.inner.get_char_list_item(addr, item_index)
+    }
+
+    fn get_char_list_iter(&self, list_addr: Self::Size) -> Self::ListIndexIterator {
+        self.inner.get_char_list_iter(list_addr)
+    }
+
+    fn get_byte_list_len(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("get_byte_list_len")?;
+        self.inner.get_byte_list_len(addr)
+    }
+
+    fn get_byte_list_item(&self, addr: Self::Size, item_index: Self::Number) -> Result<Self::Byte, Self::Error> {
+        self.check("get_byte_list_item")?;
+        self.inner.get_byte_list_item(addr, item_index)
+    }
+
+    fn get_byte_list_iter(&self, list_addr: Self::Size) -> Self::ListIndexIterator {
+        self.inner.get_byte_list_iter(list_addr)
+    }
+
+    fn add_unit(&mut self) -> Result<Self::Size, Self::Error> {
+        self.check("add_unit")?;
+        self.inner.add_unit()
+    }
+
+    fn add_true(&mut self) -> Result<Self::Size, Self::Error> {
+        self.check("add_true")?;
+        self.inner.add_true()
+    }
+
+    fn add_false(&mut self) -> Result<Self::Size, Self::Error> {
+        self.check("add_false")?;
+        self.inner.add_false()
+    }
+
+    fn add_number(&mut self, value: Self::Number) -> Result<Self::Size, Self::Error> {
+        self.check("add_number")?;
+        self.inner.add_number(value)
+    }
+
+    fn add_type(&mut self, value: GarnishDataType) -> Result<Self::Size, Self::Error> {
+        self.check("add_type")?;
+        self.inner.add_type(value)
+    }
+
+    fn add_char(&mut self, value: Self::Char) -> Result<Self::Size, Self::Error> {
+        self.check("add_char")?;
+        self.inner.add_char(value)
+    }
+
+    fn add_byte(&mut self, value: Self::Byte) -> Result<Self::Size, Self::Error> {
+        self.check("add_byte")?;
+        self.inner.add_byte(value)
+    }
+
+    fn add_symbol(&mut self, value: Self::Symbol) -> Result<Self::Size, Self::Error> {
+        self.check("add_symbol")?;
+        self.inner.add_symbol(value)
+    }
+
+    fn add_expression(&mut self, value: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_expression")?;
+        self.inner.add_expression(value)
+    }
+
+    fn add_external(&mut self, value: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_external")?;
+        self.inner.add_external(value)
+    }
+
+    fn add_pair(&mut self, value: (Self::Size, Self::Size)) -> Result<Self::Size, Self::Error> {
+        self.check("add_pair")?;
+        self.inner.add_pair(value)
+    }
+
+    fn add_concatenation(&mut self, left: Self::Size, right: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_concatenation")?;
+        self.inner.add_concatenation(left, right)
+    }
+
+    fn add_range(&mut self, start: Self::Size, end: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_range")?;
+        self.inner.add_range(start, end)
+    }
+
+    fn add_slice(&mut self, list: Self::Size, range: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_slice")?;
+        self.inner.add_slice(list, range)
+    }
+
+    fn start_list(&mut self, len: Self::Size) -> Result<(), Self::Error> {
+        self.check("start_list")?;
+        self.inner.start_list(len)
+    }
+
+    fn add_to_list(&mut self, addr: Self::Size, is_associative: bool) -> Result<(), Self::Error> {
+        self.check("add_to_list")?;
+        self.inner.add_to_list(addr, is_associative)
+    }
+
+    fn end_list(&mut self) -> Result<Self::Size, Self::Error> {
+        self.check("end_list")?;
+        self.inner.end_list()
+    }
+
+    fn start_char_list(&mut self) -> Result<(), Self::Error> {
+        self.check("start_char_list")?;
+        self.inner.start_char_list()
+    }
+
+    fn add_to_char_list(&mut self, c: Self::Char) -> Result<(), Self::Error> {
+        self.check("add_to_char_list")?;
+        self.inner.add_to_char_list(c)
+    }
+
+    fn end_char_list(&mut self) -> Result<Self::Size, Self::Error> {
+        self.check("end_char_list")?;
+        self.inner.end_char_list()
+    }
+
+    fn start_byte_list(&mut self) -> Result<(), Self::Error> {
+        self.check("start_byte_list")?;
+        self.inner.start_byte_list()
+    }
+
+    fn add_to_byte_list(&mut self, c: Self::Byte) -> Result<(), Self::Error> {
+        self.check("add_to_byte_list")?;
+        self.inner.add_to_byte_list(c)
+    }
+
+    fn end_byte_list(&mut self) -> Result<Self::Size, Self::Error> {
+        self.check("end_byte_list")?;
+        self.inner.end_byte_list()
+    }
+
+    fn get_register_len(&self) -> Self::Size {
+        self.inner.get_register_len()
+    }
+
+    fn push_register(&mut self, addr: Self::Size) -> Result<(), Self::Error> {
+        self.check("push_register")?;
+        self.inner.push_register(addr)
+    }
+
+    fn get_register(&self, addr: Self::Size) -> Option<Self::Size> {
+        self.inner.get_register(addr)
+    }
+
+    fn pop_register(&mut self) -> Result<Option<Self::Size>, Self::Error> {
+        self.check("pop_register")?;
+        self.inner.pop_register()
+    }
+
+    fn get_register_iter(&self) -> Self::RegisterIndexIterator {
+        self.inner.get_register_iter()
+    }
+
+    fn get_instruction_len(&self) -> Self::Size {
+        self.inner.get_instruction_len()
+    }
+
+    fn push_instruction(&mut self, instruction: Instruction, data: Option<Self::Size>) -> Result<Self::Size, Self::Error> {
+        self.check("push_instruction")?;
+        self.inner.push_instruction(instruction, data)
+    }
+
+    fn get_instruction(&self, addr: Self::Size) -> Option<(Instruction, Option<Self::Size>)> {
+        self.inner.get_instruction(addr)
+    }
+
+    fn get_instruction_iter(&self) -> Self::InstructionIterator {
+        self.inner.get_instruction_iter()
+    }
+
+    fn get_instruction_cursor(&self) -> Self::Size {
+        self.inner.get_instruction_cursor()
+    }
+
+    fn set_instruction_cursor(&mut self, addr: Self::Size) -> Result<(), Self::Error> {
+        self.check("set_instruction_cursor")?;
+        self.inner.set_instruction_cursor(addr)
+    }
+
+    fn get_jump_table_len(&self) -> Self::Size {
+        self.inner.get_jump_table_len()
+    }
+
+    fn push_jump_point(&mut self, index: Self::Size) -> Result<(), Self::Error> {
+        self.check("push_jump_point")?;
+        self.inner.push_jump_point(index)
+    }
+
+    fn get_jump_point(&self, index: Self::Size) -> Option<Self::Size> {
+        self.inner.get_jump_point(index)
+    }
+
+    fn get_jump_point_mut(&mut self, index: Self::Size) -> Option<&mut Self::Size> {
+        self.inner.get_jump_point_mut(index)
+    }
+
+    fn get_jump_table_iter(&self) -> Self::JumpTableIndexIterator {
+        self.inner.get_jump_table_iter()
+    }
+
+    fn push_jump_path(&mut self, index: Self::Size) -> Result<(), Self::Error> {
+        self.check("push_jump_path")?;
+        self.inner.push_jump_path(index)
+    }
+
+    fn pop_jump_path(&mut self) -> Option<Self::Size> {
+        self.inner.pop_jump_path()
+    }
+
+    fn get_jump_path_iter(&self) -> Self::JumpPathIndexIterator {
+        self.inner.get_jump_path_iter()
+    }
+
+    fn size_to_number(from: Self::Size) -> Self::Number {
+        Inner::size_to_number(from)
+    }
+
+    fn number_to_size(from: Self::Number) -> Option<Self::Size> {
+        Inner::number_to_size(from)
+    }
+
+    fn number_to_char(from: Self::Number) -> Option<Self::Char> {
+        Inner::number_to_char(from)
+    }
+
+    fn number_to_byte(from: Self::Number) -> Option<Self::Byte> {
+        Inner::number_to_byte(from)
+    }
+
+    fn char_to_number(from: Self::Char) -> Option<Self::Number> {
+        Inner::char_to_number(from)
+    }
+
+    fn char_to_byte(from: Self::Char) -> Option<Self::Byte> {
+        Inner::char_to_byte(from)
+    }
+
+    fn byte_to_number(from: Self::Byte) -> Option<Self::Number> {
+        Inner::byte_to_number(from)
+    }
+
+    fn byte_to_char(from: Self::Byte) -> Option<Self::Char> {
+        Inner::byte_to_char(from)
+    }
+
+    fn add_char_list_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_char_list_from")?;
+        self.inner.add_char_list_from(from)
+    }
+
+    fn add_byte_list_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_byte_list_from")?;
+        self.inner.add_byte_list_from(from)
+    }
+
+    fn add_symbol_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_symbol_from")?;
+        self.inner.add_symbol_from(from)
+    }
+
+    fn add_byte_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_byte_from")?;
+        self.inner.add_byte_from(from)
+    }
+
+    fn add_number_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        self.check("add_number_from")?;
+        self.inner.add_number_from(from)
+    }
+
+    fn parse_number(from: &str) -> Result<Self::Number, Self::Error> {
+        Inner::parse_number(from)
+    }
+
+    fn parse_symbol(from: &str) -> Result<Self::Symbol, Self::Error> {
+        Inner::parse_symbol(from)
+    }
+
+    fn parse_char(from: &str) -> Result<Self::Char, Self::Error> {
+        Inner::parse_char(from)
+    }
+
+    fn parse_byte(from: &str) -> Result<Self::Byte, Self::Error> {
+        Inner::parse_byte(from)
+    }
+
+    fn parse_char_list(from: &str) -> Result<Vec<Self::Char>, Self::Error> {
+        Inner::parse_char_list(from)
+    }
+
+    fn parse_byte_list(from: &str) -> Result<Vec<Self::Byte>, Self::Error> {
+        Inner::parse_byte_list(from)
+    }
+
+    fn make_size_iterator_range(min: Self::Size, max: Self::Size) -> Self::SizeIterator {
+        Inner::make_size_iterator_range(min, max)
+    }
+
+    fn make_number_iterator_range(min: Self::Number, max: Self::Number) -> Self::NumberIterator {
+        Inner::make_number_iterator_range(min, max)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use garnish_lang_simple_data::{DataError, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, GarnishDataType};
+    use crate::data::clone_data;
+    use crate::fault::FaultyData;
+    use crate::mock::MockData;
+    use crate::iterate_concatentation;
+
+    fn error() -> DataError {
+        DataError::from("fault".to_string())
+    }
+
+    #[test]
+    fn fail_nth_call() {
+        let mut data = FaultyData::new(SimpleGarnishData::new());
+        data.fail_nth_call(1, error());
+
+        assert!(data.add_unit().is_ok());
+        assert_eq!(data.add_unit().unwrap_err().to_string(), "fault");
+        assert!(data.add_unit().is_ok());
+        assert_eq!(data.call_count(), 3);
+        assert_eq!(data.fault_count(), 1);
+    }
+
+    #[test]
+    fn fail_method() {
+        let mut data = FaultyData::new(SimpleGarnishData::new());
+        data.fail_method("add_true", error);
+
+        assert!(data.add_unit().is_ok());
+        assert!(data.add_true().is_err());
+        assert!(data.add_true().is_err());
+        assert_eq!(data.fault_count(), 2);
+    }
+
+    #[test]
+    fn clone_data_propagates_read_error() {
+        let mut from = FaultyData::new(MockData::wrap(SimpleGarnishData::new()));
+        let addr = from.parse_add_char_list("\"abc\"").unwrap();
+        from.fail_when(|method, _| method == "get_char_list_item", error);
+
+        let mut to = FaultyData::new(MockData::wrap(SimpleGarnishData::new()));
+
+        assert!(clone_data(addr, &from, &mut to).is_err());
+        assert_eq!(to.inner().call_count("start_char_list"), 0);
+    }
+
+    #[test]
+    fn clone_data_propagates_write_error() {
+        let mut from = SimpleGarnishData::new();
+        let d1 = from.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = from.add_number(SimpleNumber::Integer(20)).unwrap();
+        from.start_list(2).unwrap();
+        from.add_to_list(d1, false).unwrap();
+        from.add_to_list(d2, false).unwrap();
+        let list = from.end_list().unwrap();
+        let from = FaultyData::new(from);
+
+        let mut to = FaultyData::new(SimpleGarnishData::new());
+        let start_len = to.get_data_len();
+        let numbers = Cell::new(0);
+        to.fail_when(
+            move |method, _| {
+                if method != "add_number" {
+                    return false;
+                }
+                numbers.set(numbers.get() + 1);
+                numbers.get() == 2
+            },
+            error,
+        );
+
+        assert!(clone_data(list, &from, &mut to).is_err());
+        assert_eq!(to.fault_count(), 1);
+
+        // first item was copied, second failed and the list was never built
+        assert_eq!(to.get_data_len(), start_len + 1);
+        assert_eq!(to.get_number(start_len).unwrap(), SimpleNumber::Integer(10));
+        assert!((0..to.get_data_len()).all(|i| to.get_data_type(i).unwrap() != GarnishDataType::List));
+    }
+
+    #[test]
+    fn iterate_concatenation_propagates_error() {
+        let mut data = FaultyData::new(SimpleGarnishData::new());
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let d3 = data.add_concatenation(d1, d2).unwrap();
+        let d4 = data.add_concatenation(d3, d1).unwrap();
+        data.fail_when(|method, _| method == "get_concatenation", error);
+
+        let mut items = vec![];
+        let result = iterate_concatentation(d4, &data, |item| items.push(item));
+
+        assert!(result.is_err());
+        assert!(items.is_empty());
+    }
+}
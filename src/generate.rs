@@ -0,0 +1,468 @@
+//! Generation of arbitrary values into any [`GarnishData`] implementation, for property based tests.
+//!
+//! Available with the `testing` feature.
+//! Generators are seeded, so they can be driven by any property testing framework or a plain loop over seeds.
+
+use garnish_lang_traits::{GarnishData, GarnishDataType, GarnishNumber, TypeConstants};
+use crate::data::{add_list, clone_data, diff_data, DataDiff};
+use crate::number::{number_from_u64, size_from_u64};
+use crate::{iterate_concatentation, iterate_rev_concatentation, ConcatenationIterator, UtilityError};
+
+/// Types that can be created through [`GarnishData`]. Invalid and Custom values can't be, so are never generated.
+pub const GENERATED_TYPES: [GarnishDataType; 16] = [
+    GarnishDataType::Unit,
+    GarnishDataType::True,
+    GarnishDataType::False,
+    GarnishDataType::Number,
+    GarnishDataType::Type,
+    GarnishDataType::Char,
+    GarnishDataType::CharList,
+    GarnishDataType::Byte,
+    GarnishDataType::ByteList,
+    GarnishDataType::Symbol,
+    GarnishDataType::Expression,
+    GarnishDataType::External,
+    GarnishDataType::Pair,
+    GarnishDataType::Range,
+    GarnishDataType::Concatenation,
+    GarnishDataType::Slice,
+];
+
+/// Bounds on the size of generated values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GeneratorLimits {
+    /// Deepest nesting of pairs, lists and other values containing other values.
+    pub max_depth: usize,
+    /// Most items in a single list, char list or byte list.
+    pub max_items: usize,
+    /// Largest number, expression or external value, and the number of distinct symbols.
+    pub max_value: u64,
+}
+
+impl Default for GeneratorLimits {
+    fn default() -> Self {
+        Self { max_depth: 4, max_items: 5, max_value: 1000 }
+    }
+}
+
+/// Seeded generator of arbitrary values. The same seed and limits always generate the same values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueGenerator {
+    state: u64,
+    limits: GeneratorLimits,
+}
+
+impl ValueGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self::with_limits(seed, GeneratorLimits::default())
+    }
+
+    pub fn with_limits(seed: u64, limits: GeneratorLimits) -> Self {
+        Self { state: seed, limits }
+    }
+
+    pub fn limits(&self) -> GeneratorLimits {
+        self.limits
+    }
+
+    /// Next value of the generator's splitmix64 sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random value less than `max`, or 0 if `max` is 0.
+    pub fn next_below(&mut self, max: u64) -> u64 {
+        match max {
+            0 => 0,
+            max => self.next_u64() % max,
+        }
+    }
+
+    /// Adds a value of a random type to the store, returning its address.
+    pub fn generate<Data: GarnishData>(&mut self, data: &mut Data) -> Result<Data::Size, Data::Error> {
+        self.generate_at_depth(data, 0)
+    }
+
+    /// Adds a value of the given type to the store, with random contents.
+    ///
+    /// Invalid and Custom can't be created through [`GarnishData`] and generate Unit instead.
+    pub fn generate_type<Data: GarnishData>(
+        &mut self,
+        data_type: GarnishDataType,
+        data: &mut Data,
+    ) -> Result<Data::Size, Data::Error> {
+        self.generate_type_at_depth(data_type, data, 0)
+    }
+
+    fn generate_at_depth<Data: GarnishData>(&mut self, data: &mut Data, depth: usize) -> Result<Data::Size, Data::Error> {
+        // containers come last in GENERATED_TYPES, followed by List
+        let data_type = match self.limits.max_depth.saturating_sub(depth) {
+            // only leaves once max depth is reached
+            0 => GENERATED_TYPES[self.next_below(GENERATED_TYPES.len() as u64 - 4) as usize],
+            // a slice's list and range are containers themselves, so need two levels
+            1 => match self.next_below(GENERATED_TYPES.len() as u64) as usize {
+                i if i == GENERATED_TYPES.len() - 1 => GarnishDataType::List,
+                i => GENERATED_TYPES[i],
+            },
+            _ => match self.next_below(GENERATED_TYPES.len() as u64 + 1) as usize {
+                i if i == GENERATED_TYPES.len() => GarnishDataType::List,
+                i => GENERATED_TYPES[i],
+            },
+        };
+
+        self.generate_type_at_depth(data_type, data, depth)
+    }
+
+    fn generate_type_at_depth<Data: GarnishData>(
+        &mut self,
+        data_type: GarnishDataType,
+        data: &mut Data,
+        depth: usize,
+    ) -> Result<Data::Size, Data::Error> {
+        match data_type {
+            GarnishDataType::Invalid | GarnishDataType::Custom | GarnishDataType::Unit => data.add_unit(),
+            GarnishDataType::True => data.add_true(),
+            GarnishDataType::False => data.add_false(),
+            GarnishDataType::Number => {
+                let number = self.number::<Data>()?;
+                data.add_number(number)
+            }
+            GarnishDataType::Type => {
+                let i = self.next_below(GENERATED_TYPES.len() as u64) as usize;
+                data.add_type(GENERATED_TYPES[i])
+            }
+            GarnishDataType::Char => {
+                let c = self.char::<Data>()?;
+                data.add_char(c)
+            }
+            GarnishDataType::Byte => {
+                let b = self.byte::<Data>()?;
+                data.add_byte(b)
+            }
+            GarnishDataType::CharList => {
+                let chars = (0..self.next_below(self.limits.max_items as u64 + 1)).map(|_| self.char::<Data>()).collect::<Result<Vec<Data::Char>, Data::Error>>()?;
+                data.start_char_list()?;
+                for c in chars {
+                    data.add_to_char_list(c)?;
+                }
+                data.end_char_list()
+            }
+            GarnishDataType::ByteList => {
+                let bytes = (0..self.next_below(self.limits.max_items as u64 + 1)).map(|_| self.byte::<Data>()).collect::<Result<Vec<Data::Byte>, Data::Error>>()?;
+                data.start_byte_list()?;
+                for b in bytes {
+                    data.add_to_byte_list(b)?;
+                }
+                data.end_byte_list()
+            }
+            GarnishDataType::Symbol => {
+                let symbol = self.symbol::<Data>()?;
+                data.add_symbol(symbol)
+            }
+            GarnishDataType::Expression => {
+                let value = self.size::<Data>()?;
+                data.add_expression(value)
+            }
+            GarnishDataType::External => {
+                let value = self.size::<Data>()?;
+                data.add_external(value)
+            }
+            GarnishDataType::Pair => {
+                let left = match self.next_below(2) {
+                    0 => self.generate_type_at_depth(GarnishDataType::Symbol, data, depth + 1)?,
+                    _ => self.generate_at_depth(data, depth + 1)?,
+                };
+                let right = self.generate_at_depth(data, depth + 1)?;
+                data.add_pair((left, right))
+            }
+            GarnishDataType::Range => {
+                let start = self.generate_type_at_depth(GarnishDataType::Number, data, depth + 1)?;
+                let end = self.generate_type_at_depth(GarnishDataType::Number, data, depth + 1)?;
+                data.add_range(start, end)
+            }
+            GarnishDataType::Concatenation => {
+                let left = self.generate_at_depth(data, depth + 1)?;
+                let right = self.generate_at_depth(data, depth + 1)?;
+                data.add_concatenation(left, right)
+            }
+            GarnishDataType::Slice => {
+                let value = self.generate_type_at_depth(GarnishDataType::List, data, depth + 1)?;
+                let range = self.generate_type_at_depth(GarnishDataType::Range, data, depth + 1)?;
+                data.add_slice(value, range)
+            }
+            GarnishDataType::List => {
+                // lists forced past max depth, by a requested type, are left empty
+                let count = match depth < self.limits.max_depth {
+                    true => self.next_below(self.limits.max_items as u64 + 1),
+                    false => 0,
+                };
+                let mut items = vec![];
+                for _ in 0..count {
+                    items.push(self.generate_at_depth(data, depth + 1)?);
+                }
+                add_list(items, data)
+            }
+        }
+    }
+
+    fn size<Data: GarnishData>(&mut self) -> Result<Data::Size, Data::Error> {
+        let value = self.next_below(self.limits.max_value + 1);
+        Ok(size_from_u64::<Data>(value)?.unwrap_or_default())
+    }
+
+    fn number<Data: GarnishData>(&mut self) -> Result<Data::Number, Data::Error> {
+        let number = number_from_u64::<Data>(self.next_below(self.limits.max_value + 1))?;
+        Ok(match self.next_below(2) {
+            0 => number,
+            _ => number.clone().opposite().unwrap_or(number),
+        })
+    }
+
+    fn char<Data: GarnishData>(&mut self) -> Result<Data::Char, Data::Error> {
+        // printable ascii
+        let value = number_from_u64::<Data>(32 + self.next_below(95))?;
+        Ok(Data::number_to_char(value).unwrap_or_default())
+    }
+
+    fn byte<Data: GarnishData>(&mut self) -> Result<Data::Byte, Data::Error> {
+        let value = number_from_u64::<Data>(self.next_below(256))?;
+        Ok(Data::number_to_byte(value).unwrap_or_default())
+    }
+
+    fn symbol<Data: GarnishData>(&mut self) -> Result<Data::Symbol, Data::Error> {
+        let value = self.next_below(self.limits.max_value + 1);
+        Data::parse_symbol(&format!("symbol_{}", value))
+    }
+}
+
+/// Adds values described by arbitrary bytes, such as fuzzer input, three bytes per value.
+///
+/// References can point at any added value, at the address the next value will be added at, or past it,
+/// so the store may contain cycles and dangling references. Stops at the first error from the store.
+pub fn build_from_bytes<Data: GarnishData>(bytes: &[u8], data: &mut Data) -> Result<(), Data::Error> {
+    let mut added: Vec<Data::Size> = vec![];
+
+    for chunk in bytes.chunks_exact(3) {
+        let (op, a, b) = (chunk[0], chunk[1], chunk[2]);
+        let reference = |i: u8, data: &Data| match i as usize % (added.len() + 2) {
+            i if i < added.len() => added[i].clone(),
+            i if i == added.len() => data.get_data_len(),
+            _ => data.get_data_len() + Data::Size::one(),
+        };
+
+        let addr = match op % 17 {
+            0 => data.add_unit()?,
+            1 => data.add_true()?,
+            2 => data.add_false()?,
+            3 => data.add_number(number_from_u64::<Data>(a as u64)?)?,
+            4 => data.add_char(Data::number_to_char(number_from_u64::<Data>(a as u64)?).unwrap_or_default())?,
+            5 => data.add_byte(Data::number_to_byte(number_from_u64::<Data>(a as u64)?).unwrap_or_default())?,
+            6 => data.add_symbol(Data::parse_symbol(&format!("symbol_{}", a))?)?,
+            7 => data.add_type(GENERATED_TYPES[a as usize % GENERATED_TYPES.len()])?,
+            8 => data.add_expression(size_from_u64::<Data>(a as u64)?.unwrap_or_default())?,
+            9 => {
+                data.start_char_list()?;
+                for i in 0..a % 8 {
+                    let c = number_from_u64::<Data>(32 + (b.wrapping_add(i) % 95) as u64)?;
+                    data.add_to_char_list(Data::number_to_char(c).unwrap_or_default())?;
+                }
+                data.end_char_list()?
+            }
+            10 => {
+                data.start_byte_list()?;
+                for i in 0..a % 8 {
+                    let byte = number_from_u64::<Data>(b.wrapping_add(i) as u64)?;
+                    data.add_to_byte_list(Data::number_to_byte(byte).unwrap_or_default())?;
+                }
+                data.end_byte_list()?
+            }
+            11 => data.add_pair((reference(a, data), reference(b, data)))?,
+            12 => data.add_range(reference(a, data), reference(b, data))?,
+            13 => data.add_concatenation(reference(a, data), reference(b, data))?,
+            14 => data.add_slice(reference(a, data), reference(b, data))?,
+            15 => {
+                let items = (0..a % 4).map(|i| reference(b.wrapping_add(i), data)).collect::<Vec<Data::Size>>();
+                data.start_list(size_from_u64::<Data>(items.len() as u64)?.unwrap_or_default())?;
+                for item in items {
+                    data.add_to_list(item, false)?;
+                }
+                data.end_list()?
+            }
+            _ => {
+                data.push_value_stack(reference(a, data))?;
+                continue;
+            }
+        };
+
+        added.push(addr);
+    }
+
+    Ok(())
+}
+
+/// Property that cloning a value into another store creates an equal value. Returns any differences between the two.
+pub fn check_clone_round_trip<Data: GarnishData>(
+    addr: Data::Size,
+    from: &Data,
+    to: &mut Data,
+) -> Result<DataDiff, UtilityError<Data::Error>> {
+    let new_addr = clone_data(addr.clone(), from, to)?;
+    diff_data(addr, from, new_addr, to)
+}
+
+/// Property that every way of iterating a concatenation gives the same items, in the same order or reversed.
+pub fn check_concatenation_iteration<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<bool, Data::Error> {
+    let mut forward = vec![];
+    iterate_concatentation(addr.clone(), data, |item| forward.push(item))?;
+
+    let mut backward = vec![];
+    iterate_rev_concatentation(addr.clone(), data, |item| backward.push(item))?;
+    backward.reverse();
+
+    let iterated = ConcatenationIterator::new(addr.clone(), data).collect::<Result<Vec<Data::Size>, Data::Error>>()?;
+    let mut rev_iterated = ConcatenationIterator::new(addr, data).rev().collect::<Result<Vec<Data::Size>, Data::Error>>()?;
+    rev_iterated.reverse();
+
+    Ok(forward == backward && forward == iterated && forward == rev_iterated)
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::SimpleGarnishData;
+    use garnish_lang_traits::{GarnishData, GarnishDataType};
+    use crate::data::{analyze_data, clone_data_checked, collect_garbage, diff_data, fork_data, restore_snapshot, take_snapshot_without_jump_path};
+    use crate::generate::{build_from_bytes, check_clone_round_trip, check_concatenation_iteration, GeneratorLimits, ValueGenerator, GENERATED_TYPES};
+    use crate::visitor::children;
+    use crate::{
+        concatenation_metrics, find_cycle, iterate_concatentation_checked, rebalance_concatenation_with_limit, remove_association, reverse_list, validate,
+        ConcatenationIterator, CyclePolicy, RebalanceStrategy, Schema,
+    };
+
+    #[test]
+    fn same_seed_same_value() {
+        let mut first = SimpleGarnishData::new();
+        let a = ValueGenerator::new(10).generate(&mut first).unwrap();
+        let mut second = SimpleGarnishData::new();
+        let b = ValueGenerator::new(10).generate(&mut second).unwrap();
+
+        assert!(diff_data(a, &first, b, &second).unwrap().is_empty());
+    }
+
+    #[test]
+    fn generates_every_type() {
+        for data_type in GENERATED_TYPES.iter().chain([GarnishDataType::List].iter()) {
+            let mut data = SimpleGarnishData::new();
+            let addr = ValueGenerator::new(1).generate_type(*data_type, &mut data).unwrap();
+
+            assert_eq!(data.get_data_type(addr).unwrap(), *data_type);
+        }
+    }
+
+    #[test]
+    fn respects_depth() {
+        let limits = GeneratorLimits { max_depth: 0, max_items: 3, max_value: 10 };
+        for seed in 0..50 {
+            let mut data = SimpleGarnishData::new();
+            let addr = ValueGenerator::with_limits(seed, limits).generate(&mut data).unwrap();
+
+            assert!(!matches!(
+                data.get_data_type(addr).unwrap(),
+                GarnishDataType::Pair | GarnishDataType::Range | GarnishDataType::Concatenation | GarnishDataType::Slice | GarnishDataType::List
+            ));
+        }
+    }
+
+    fn depth(addr: usize, data: &SimpleGarnishData) -> usize {
+        let data_type = data.get_data_type(addr).unwrap();
+        children(addr, data_type, data).unwrap().into_iter().map(|child| depth(child, data) + 1).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn respects_depth_of_forced_children() {
+        for max_depth in 0..4 {
+            let limits = GeneratorLimits { max_depth, max_items: 3, max_value: 10 };
+            for seed in 0..200 {
+                let mut data = SimpleGarnishData::new();
+                let addr = ValueGenerator::with_limits(seed, limits).generate(&mut data).unwrap();
+
+                assert!(depth(addr, &data) <= max_depth, "seed {} with max depth {}", seed, max_depth);
+            }
+        }
+    }
+
+    #[test]
+    fn clone_round_trip() {
+        for seed in 0..100 {
+            let mut from = SimpleGarnishData::new();
+            let addr = ValueGenerator::new(seed).generate(&mut from).unwrap();
+            let mut to = SimpleGarnishData::new();
+
+            let diff = check_clone_round_trip(addr, &from, &mut to).unwrap();
+
+            assert!(diff.is_empty(), "seed {}\n{}", seed, diff);
+        }
+    }
+
+    #[test]
+    fn concatenation_iteration() {
+        for seed in 0..100 {
+            let mut data = SimpleGarnishData::new();
+            let addr = ValueGenerator::new(seed).generate_type(GarnishDataType::Concatenation, &mut data).unwrap();
+
+            assert!(check_concatenation_iteration(addr, &data).unwrap(), "seed {}", seed);
+        }
+    }
+
+    // same checks as the fuzz targets, over pseudo random input
+    #[test]
+    fn malformed_data_is_bounded() {
+        for seed in 0..200 {
+            let mut generator = ValueGenerator::new(seed);
+            let bytes = (0..60).map(|_| generator.next_u64() as u8).collect::<Vec<u8>>();
+
+            let mut data = SimpleGarnishData::new();
+            let _ = build_from_bytes(&bytes, &mut data);
+
+            for addr in data.get_data_iter() {
+                let _ = find_cycle(addr, &data);
+                let _ = clone_data_checked(addr, &data, &mut SimpleGarnishData::new());
+                let _ = ConcatenationIterator::with_work_limit(addr, &data, 1000).count();
+                let _ = iterate_concatentation_checked(addr, &data, CyclePolicy::YieldOnce, |_| ());
+            }
+
+            let _ = analyze_data(&data, 5);
+
+            if let Ok(snapshot) = take_snapshot_without_jump_path(&data) {
+                let _ = restore_snapshot(&snapshot, &mut SimpleGarnishData::new());
+            }
+
+            let _ = collect_garbage(&data, &mut SimpleGarnishData::new());
+            let _ = fork_data(&data, &mut SimpleGarnishData::new());
+
+            let addrs = data.get_data_iter().collect::<Vec<usize>>();
+            for addr in addrs {
+                let _ = concatenation_metrics(addr, &data);
+                let _ = diff_data(addr, &data, addr, &data);
+                let _ = validate(addr, &data, &Schema::list_of(Schema::Any));
+                let _ = rebalance_concatenation_with_limit(addr, &mut data, RebalanceStrategy::Balanced, 1000);
+                let _ = reverse_list(addr, &mut data);
+                let _ = remove_association(addr, 0, &mut data);
+            }
+        }
+    }
+
+    #[test]
+    fn build_self_reference() {
+        // concatenation of itself and the unit before it
+        let mut data = SimpleGarnishData::new();
+        build_from_bytes(&[0, 0, 0, 13, 1, 0], &mut data).unwrap();
+
+        let addr = data.get_data_len() - 1;
+        assert_eq!(data.get_concatenation(addr).unwrap(), (addr, 0));
+        assert_eq!(find_cycle(addr, &data).unwrap(), Some(addr));
+    }
+}
@@ -1,4 +1,36 @@
+mod address_map;
+mod address_set;
+mod association;
+mod builder;
 mod concatenation;
 pub mod data;
+mod error;
+#[cfg(any(test, feature = "mock"))]
+pub mod fault;
+#[cfg(any(test, feature = "testing"))]
+pub mod generate;
+mod list;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+mod number;
+mod pattern;
+mod range;
+mod schema;
+mod slice;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod text;
+mod visitor;
 
+pub use address_map::*;
+pub use association::*;
+pub use builder::*;
 pub use concatenation::*;
+pub use error::*;
+pub use list::*;
+pub use pattern::*;
+pub use range::*;
+pub use schema::*;
+pub use slice::*;
+pub use text::*;
+pub use visitor::*;
@@ -0,0 +1,220 @@
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::data::add_list;
+use crate::{iterate_concatentation_checked, CyclePolicy, SliceItem, SliceIterator, UtilityError};
+
+/// Addresses of the items of a List, Concatenation or Slice, in order.
+///
+/// Concatenations are flattened the same as [`iterate_concatentation`](crate::iterate_concatentation).
+/// Slices are read with [`SliceIterator`]. Fails with [`UtilityError::Cycle`] if a concatenation or slice refers back to itself.
+pub fn list_items<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Vec<Data::Size>, UtilityError<Data::Error>> {
+    let mut items = vec![];
+    match data.get_data_type(addr.clone())? {
+        GarnishDataType::List | GarnishDataType::Concatenation => {
+            iterate_concatentation_checked(addr, data, CyclePolicy::Error, |item| items.push(item))?;
+        }
+        GarnishDataType::Slice => {
+            let iter = SliceIterator::new(addr.clone(), data)?;
+            match iter.source_type() {
+                GarnishDataType::List | GarnishDataType::Concatenation => {
+                    for item in iter {
+                        if let SliceItem::Item(item) = item? {
+                            items.push(item);
+                        }
+                    }
+                }
+                t => Err(UtilityError::UnsupportedType(format!("slice at {} is of {:?}, expected List or Concatenation", addr, t)))?,
+            }
+        }
+        t => Err(UtilityError::UnsupportedType(format!("value at {} is {:?}, expected List, Concatenation or Slice", addr, t)))?,
+    }
+
+    Ok(items)
+}
+
+/// Creates a new list of each item passed through `map_fn`.
+pub fn map_list<Data: GarnishData, MapFn: FnMut(Data::Size, &mut Data) -> Result<Data::Size, Data::Error>>(
+    addr: Data::Size,
+    data: &mut Data,
+    mut map_fn: MapFn,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let mut mapped = vec![];
+    for item in list_items(addr, data)? {
+        mapped.push(map_fn(item, data)?);
+    }
+
+    Ok(add_list(mapped, data)?)
+}
+
+/// Creates a new list of the items `filter_fn` returns true for.
+pub fn filter_list<Data: GarnishData, FilterFn: FnMut(Data::Size, &Data) -> Result<bool, Data::Error>>(
+    addr: Data::Size,
+    data: &mut Data,
+    mut filter_fn: FilterFn,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let mut kept = vec![];
+    for item in list_items(addr, data)? {
+        if filter_fn(item.clone(), data)? {
+            kept.push(item);
+        }
+    }
+
+    Ok(add_list(kept, data)?)
+}
+
+/// Combines the items, from first to last, into a single host value.
+pub fn fold_list<Data: GarnishData, T, FoldFn: FnMut(T, Data::Size, &mut Data) -> Result<T, Data::Error>>(
+    addr: Data::Size,
+    data: &mut Data,
+    init: T,
+    mut fold_fn: FoldFn,
+) -> Result<T, UtilityError<Data::Error>> {
+    let mut acc = init;
+    for item in list_items(addr, data)? {
+        acc = fold_fn(acc, item, data)?;
+    }
+
+    Ok(acc)
+}
+
+/// Creates a new list of pairs of items at the same position, ending with the shorter list.
+pub fn zip_lists<Data: GarnishData>(
+    left: Data::Size,
+    right: Data::Size,
+    data: &mut Data,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    zip_lists_with(left, right, data, |left, right, data| data.add_pair((left, right)))
+}
+
+/// Creates a new list of items at the same position combined by `zip_fn`, ending with the shorter list.
+pub fn zip_lists_with<Data: GarnishData, ZipFn: FnMut(Data::Size, Data::Size, &mut Data) -> Result<Data::Size, Data::Error>>(
+    left: Data::Size,
+    right: Data::Size,
+    data: &mut Data,
+    mut zip_fn: ZipFn,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let left_items = list_items(left, data)?;
+    let right_items = list_items(right, data)?;
+
+    let mut zipped = vec![];
+    for (left, right) in left_items.into_iter().zip(right_items) {
+        zipped.push(zip_fn(left, right, data)?);
+    }
+
+    Ok(add_list(zipped, data)?)
+}
+
+/// Creates a new list of the items in reverse order.
+pub fn reverse_list<Data: GarnishData>(addr: Data::Size, data: &mut Data) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let mut items = list_items(addr, data)?;
+    items.reverse();
+
+    Ok(add_list(items, data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleData, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, GarnishDataType};
+    use crate::testing::GarnishValue;
+    use crate::{assert_garnish_eq, filter_list, fold_list, list_items, map_list, reverse_list, zip_lists, UtilityError, ValueBuilder};
+
+    fn number(n: i32) -> GarnishValue<SimpleNumber, char, u8, u64, usize> {
+        GarnishValue::Number(SimpleNumber::Integer(n))
+    }
+
+    fn make_list(data: &mut SimpleGarnishData) -> usize {
+        ValueBuilder::new(data)
+            .list(|l| l.assoc("a", |v| v.number(1.into())).item(|v| v.number(2.into())).assoc("b", |v| v.number(3.into())))
+            .unwrap()
+    }
+
+    #[test]
+    fn map() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data);
+
+        let mapped = map_list(list, &mut data, |item, data| match data.get_data_type(item)? {
+            GarnishDataType::Pair => Ok(data.get_pair(item)?.1),
+            _ => data.add_unit(),
+        })
+        .unwrap();
+
+        assert_garnish_eq!(data, mapped, GarnishValue::List(vec![number(1), GarnishValue::Unit, number(3)]));
+    }
+
+    #[test]
+    fn filter_keeps_associations() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data);
+        let sym = data.parse_add_symbol("b").map(|addr| data.get_symbol(addr).unwrap()).unwrap();
+
+        let filtered = filter_list(list, &mut data, |item, data| Ok(data.get_data_type(item)? == GarnishDataType::Pair)).unwrap();
+
+        assert_eq!(data.get_list_len(filtered).unwrap(), 2);
+        assert_eq!(data.get_list_associations_len(filtered).unwrap(), 2);
+        assert!(data.get_list_item_with_symbol(filtered, sym).unwrap().is_some());
+    }
+
+    #[test]
+    fn fold() {
+        let mut data = SimpleGarnishData::new();
+        let list = ValueBuilder::new(&mut data).list(|l| l.items(1..=4, |v, n| v.number(n.into()))).unwrap();
+
+        let sum = fold_list(list, &mut data, 0, |acc, item, data| match data.get_number(item)? {
+            SimpleNumber::Integer(n) => Ok(acc + n),
+            SimpleNumber::Float(_) => Ok(acc),
+        })
+        .unwrap();
+
+        assert_eq!(sum, 10);
+    }
+
+    #[test]
+    fn zip_and_reverse_concatenation() {
+        let mut data = SimpleGarnishData::new();
+        let left = ValueBuilder::new(&mut data)
+            .concatenation(|v| v.number(1.into()), |v| v.list(|l| l.items(2..=3, |v, n| v.number(n.into()))))
+            .unwrap();
+        let right = ValueBuilder::new(&mut data).list(|l| l.items(4..=5, |v, n| v.number(n.into()))).unwrap();
+
+        let zipped = zip_lists(left, right, &mut data).unwrap();
+        let reversed = reverse_list(left, &mut data).unwrap();
+
+        assert_garnish_eq!(
+            data,
+            zipped,
+            GarnishValue::List(vec![GarnishValue::pair(number(1), number(4)), GarnishValue::pair(number(2), number(5))])
+        );
+        assert_garnish_eq!(data, reversed, GarnishValue::List(vec![number(3), number(2), number(1)]));
+    }
+
+    #[test]
+    fn slice_items() {
+        let mut data = SimpleGarnishData::new();
+        let slice = ValueBuilder::new(&mut data)
+            .slice(
+                |v| v.list(|l| l.items(0..5, |v, n| v.number(n.into()))),
+                |v| v.range(|v| v.number(1.into()), |v| v.number(3.into())),
+            )
+            .unwrap();
+        let open = ValueBuilder::new(&mut data)
+            .slice(|v| v.value(slice), |v| v.range(|v| v.number(1.into()), |v| v.unit()))
+            .unwrap();
+
+        let reversed = reverse_list(slice, &mut data).unwrap();
+
+        assert_garnish_eq!(data, reversed, GarnishValue::List(vec![number(3), number(2), number(1)]));
+        assert_eq!(list_items(open, &data).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn unsupported_and_cyclic() {
+        let mut data = SimpleGarnishData::new();
+        let num = data.add_number(1.into()).unwrap();
+        let next = data.get_data_len();
+        data.get_data_mut().push(SimpleData::Concatenation(num, next));
+
+        assert!(matches!(list_items(num, &data), Err(UtilityError::UnsupportedType(_))));
+        assert!(matches!(list_items(next, &data), Err(UtilityError::Cycle(_))));
+    }
+}
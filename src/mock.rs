@@ -0,0 +1,718 @@
+//! Recording [`GarnishData`] implementation for tests of code that is generic over the data trait.
+//!
+//! Available with the `mock` feature.
+
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use garnish_lang_traits::{GarnishData, GarnishDataType, Instruction};
+
+/// A single call made to a [`MockData`], with each argument formatted with [`std::fmt::Debug`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockCall {
+    pub method: &'static str,
+    pub args: Vec<String>,
+}
+
+impl Display for MockCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.method, self.args.join(", "))
+    }
+}
+
+/// Scripted return value for a call to a [`MockData`].
+///
+/// The variant must match the return type of the method it's returned from, otherwise the call panics.
+/// [`MockResponse::Error`] can only be returned from methods that return a [`Result`].
+pub enum MockResponse<Data: GarnishData> {
+    Error(Data::Error),
+    /// Successful result of a method that returns nothing.
+    Ok,
+    Size(Data::Size),
+    OptionalSize(Option<Data::Size>),
+    Number(Data::Number),
+    Char(Data::Char),
+    Byte(Data::Byte),
+    Symbol(Data::Symbol),
+    DataType(GarnishDataType),
+    Pair(Data::Size, Data::Size),
+    Instruction(Option<(Instruction, Option<Data::Size>)>),
+}
+
+impl<Data: GarnishData> MockResponse<Data> {
+    fn name(&self) -> &'static str {
+        match self {
+            MockResponse::Error(_) => "Error",
+            MockResponse::Ok => "Ok",
+            MockResponse::Size(_) => "Size",
+            MockResponse::OptionalSize(_) => "OptionalSize",
+            MockResponse::Number(_) => "Number",
+            MockResponse::Char(_) => "Char",
+            MockResponse::Byte(_) => "Byte",
+            MockResponse::Symbol(_) => "Symbol",
+            MockResponse::DataType(_) => "DataType",
+            MockResponse::Pair(_, _) => "Pair",
+            MockResponse::Instruction(_) => "Instruction",
+        }
+    }
+}
+
+impl<Data: GarnishData> Clone for MockResponse<Data>
+where
+    Data::Error: Clone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            MockResponse::Error(e) => MockResponse::Error(e.clone()),
+            MockResponse::Ok => MockResponse::Ok,
+            MockResponse::Size(v) => MockResponse::Size(v.clone()),
+            MockResponse::OptionalSize(v) => MockResponse::OptionalSize(v.clone()),
+            MockResponse::Number(v) => MockResponse::Number(v.clone()),
+            MockResponse::Char(v) => MockResponse::Char(v.clone()),
+            MockResponse::Byte(v) => MockResponse::Byte(v.clone()),
+            MockResponse::Symbol(v) => MockResponse::Symbol(v.clone()),
+            MockResponse::DataType(v) => MockResponse::DataType(*v),
+            MockResponse::Pair(l, r) => MockResponse::Pair(l.clone(), r.clone()),
+            MockResponse::Instruction(v) => MockResponse::Instruction(v.clone()),
+        }
+    }
+}
+
+/// Function deciding the response to a call, given the call and how many previous calls were made to the same method.
+pub type MockScript<Data> = Box<dyn Fn(&MockCall, usize) -> Option<MockResponse<Data>>>;
+
+/// [`GarnishData`] implementation that records every call made to it.
+///
+/// Calls are answered by the first script that returns a response, then by the inner data if there is one.
+/// Calls without a response panic, as do calls with no scripts available (iterators and mutable references) when there is no inner data.
+pub struct MockData<Inner: GarnishData> {
+    inner: Option<Inner>,
+    calls: RefCell<Vec<MockCall>>,
+    scripts: Vec<MockScript<Inner>>,
+}
+
+impl<Inner: GarnishData> MockData<Inner> {
+    /// Mock without inner data, all calls need to be scripted.
+    pub fn new() -> Self {
+        Self { inner: None, calls: RefCell::new(vec![]), scripts: vec![] }
+    }
+
+    /// Mock that passes calls without a scripted response to the given data.
+    pub fn wrap(inner: Inner) -> Self {
+        Self { inner: Some(inner), calls: RefCell::new(vec![]), scripts: vec![] }
+    }
+
+    pub fn inner(&self) -> Option<&Inner> {
+        self.inner.as_ref()
+    }
+
+    pub fn inner_mut(&mut self) -> Option<&mut Inner> {
+        self.inner.as_mut()
+    }
+
+    pub fn into_inner(self) -> Option<Inner> {
+        self.inner
+    }
+
+    pub fn respond_with<F: Fn(&MockCall, usize) -> Option<MockResponse<Inner>> + 'static>(&mut self, script: F) {
+        self.scripts.push(Box::new(script));
+    }
+
+    /// Responds to every call of the given method.
+    pub fn respond(&mut self, method: &'static str, response: MockResponse<Inner>)
+    where
+        Inner::Error: Clone,
+        Inner: 'static,
+    {
+        self.respond_with(move |call, _| (call.method == method).then(|| response.clone()));
+    }
+
+    /// Responds to only the nth call, starting at 0, of the given method.
+    pub fn respond_nth(&mut self, method: &'static str, n: usize, response: MockResponse<Inner>)
+    where
+        Inner: 'static,
+    {
+        let response = RefCell::new(Some(response));
+        self.respond_with(move |call, count| match call.method == method && count == n {
+            true => response.borrow_mut().take(),
+            false => None,
+        });
+    }
+
+    /// Fails every call of the given method.
+    pub fn fail(&mut self, method: &'static str, error: Inner::Error)
+    where
+        Inner::Error: Clone,
+        Inner: 'static,
+    {
+        self.respond(method, MockResponse::Error(error));
+    }
+
+    /// Fails only the nth call, starting at 0, of the given method.
+    pub fn fail_nth(&mut self, method: &'static str, n: usize, error: Inner::Error)
+    where
+        Inner: 'static,
+    {
+        self.respond_nth(method, n, MockResponse::Error(error));
+    }
+
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls.borrow().clone()
+    }
+
+    pub fn calls_to(&self, method: &str) -> Vec<MockCall> {
+        self.calls.borrow().iter().filter(|call| call.method == method).cloned().collect()
+    }
+
+    pub fn call_count(&self, method: &str) -> usize {
+        self.calls.borrow().iter().filter(|call| call.method == method).count()
+    }
+
+    pub fn clear_calls(&mut self) {
+        self.calls.borrow_mut().clear();
+    }
+
+    fn record(&self, method: &'static str, args: Vec<String>) -> Option<MockResponse<Inner>> {
+        let call = MockCall { method, args };
+        let count = self.call_count(method);
+        let response = self.scripts.iter().find_map(|script| script(&call, count));
+        self.calls.borrow_mut().push(call);
+        response
+    }
+
+    fn inner_ref(&self, method: &str) -> &Inner {
+        match self.inner.as_ref() {
+            Some(inner) => inner,
+            None => panic!("MockData has no response for {} and no inner data", method),
+        }
+    }
+
+    fn inner_mut_ref(&mut self, method: &str) -> &mut Inner {
+        match self.inner.as_mut() {
+            Some(inner) => inner,
+            None => panic!("MockData has no response for {} and no inner data", method),
+        }
+    }
+}
+
+impl<Inner: GarnishData> Default for MockData<Inner> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn unexpected<Data: GarnishData, T>(method: &str, response: MockResponse<Data>) -> T {
+    panic!("MockData can't return {} response from {}", response.name(), method)
+}
+
+macro_rules! mock_call {
+    // delegate to inner without scripts
+    ($self:ident.$inner:ident.$method:ident($($arg:expr),*)) => {{
+        $self.record(stringify!($method), vec![$(format!("{:?}", $arg)),*]);
+        $self.$inner(stringify!($method)).$method($($arg),*)
+    }};
+    ($self:ident.$inner:ident.$method:ident($($arg:expr),*) -> Ok) => {
+        match $self.record(stringify!($method), vec![$(format!("{:?}", $arg)),*]) {
+            Some(MockResponse::Ok) => Ok(()),
+            Some(MockResponse::Error(e)) => Err(e),
+            Some(r) => unexpected(stringify!($method), r),
+            None => $self.$inner(stringify!($method)).$method($($arg),*),
+        }
+    };
+    ($self:ident.$inner:ident.$method:ident($($arg:expr),*) -> Pair) => {
+        match $self.record(stringify!($method), vec![$(format!("{:?}", $arg)),*]) {
+            Some(MockResponse::Pair(l, r)) => Ok((l, r)),
+            Some(MockResponse::Error(e)) => Err(e),
+            Some(r) => unexpected(stringify!($method), r),
+            None => $self.$inner(stringify!($method)).$method($($arg),*),
+        }
+    };
+    ($self:ident.$inner:ident.$method:ident($($arg:expr),*) -> $variant:ident) => {
+        match $self.record(stringify!($method), vec![$(format!("{:?}", $arg)),*]) {
+            Some(MockResponse::$variant(v)) => Ok(v),
+            Some(MockResponse::Error(e)) => Err(e),
+            Some(r) => unexpected(stringify!($method), r),
+            None => $self.$inner(stringify!($method)).$method($($arg),*),
+        }
+    };
+    // methods that don't return a result
+    ($self:ident.$inner:ident.$method:ident($($arg:expr),*) => $variant:ident) => {
+        match $self.record(stringify!($method), vec![$(format!("{:?}", $arg)),*]) {
+            Some(MockResponse::$variant(v)) => v,
+            Some(r) => unexpected(stringify!($method), r),
+            None => $self.$inner(stringify!($method)).$method($($arg),*),
+        }
+    };
+}
+
+impl<Inner: GarnishData> GarnishData for MockData<Inner> {
+    type Error = Inner::Error;
+    type Symbol = Inner::Symbol;
+    type Byte = Inner::Byte;
+    type Char = Inner::Char;
+    type Number = Inner::Number;
+    type Size = Inner::Size;
+    type SizeIterator = Inner::SizeIterator;
+    type NumberIterator = Inner::NumberIterator;
+    type InstructionIterator = Inner::InstructionIterator;
+    type DataIndexIterator = Inner::DataIndexIterator;
+    type ValueIndexIterator = Inner::ValueIndexIterator;
+    type RegisterIndexIterator = Inner::RegisterIndexIterator;
+    type JumpTableIndexIterator = Inner::JumpTableIndexIterator;
+    type JumpPathIndexIterator = Inner::JumpPathIndexIterator;
+    type ListIndexIterator = Inner::ListIndexIterator;
+
+    fn get_data_len(&self) -> Self::Size {
+        mock_call!(self.inner_ref.get_data_len() => Size)
+    }
+
+    fn get_data_iter(&self) -> Self::DataIndexIterator {
+        mock_call!(self.inner_ref.get_data_iter())
+    }
+
+    fn get_value_stack_len(&self) -> Self::Size {
+        mock_call!(self.inner_ref.get_value_stack_len() => Size)
+    }
+
+    fn push_value_stack(&mut self, addr: Self::Size) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.push_value_stack(addr) -> Ok)
+    }
+
+    fn pop_value_stack(&mut self) -> Option<Self::Size> {
+        mock_call!(self.inner_mut_ref.pop_value_stack() => OptionalSize)
+    }
+
+    fn get_value(&self, addr: Self::Size) -> Option<Self::Size> {
+        mock_call!(self.inner_ref.get_value(addr) => OptionalSize)
+    }
+
+    fn get_value_mut(&mut self, addr: Self::Size) -> Option<&mut Self::Size> {
+        mock_call!(self.inner_mut_ref.get_value_mut(addr))
+    }
+
+    fn get_current_value(&self) -> Option<Self::Size> {
+        mock_call!(self.inner_ref.get_current_value() => OptionalSize)
+    }
+
+    fn get_current_value_mut(&mut self) -> Option<&mut Self::Size> {
+        mock_call!(self.inner_mut_ref.get_current_value_mut())
+    }
+
+    fn get_value_iter(&self) -> Self::ValueIndexIterator {
+        mock_call!(self.inner_ref.get_value_iter())
+    }
+
+    fn get_data_type(&self, addr: Self::Size) -> Result<GarnishDataType, Self::Error> {
+        mock_call!(self.inner_ref.get_data_type(addr) -> DataType)
+    }
+
+    fn get_number(&self, addr: Self::Size) -> Result<Self::Number, Self::Error> {
+        mock_call!(self.inner_ref.get_number(addr) -> Number)
+    }
+
+    fn get_type(&self, addr: Self::Size) -> Result<GarnishDataType, Self::Error> {
+        mock_call!(self.inner_ref.get_type(addr) -> DataType)
+    }
+
+    fn get_char(&self, addr: Self::Size) -> Result<Self::Char, Self::Error> {
+        mock_call!(self.inner_ref.get_char(addr) -> Char)
+    }
+
+    fn get_byte(&self, addr: Self::Size) -> Result<Self::Byte, Self::Error> {
+        mock_call!(self.inner_ref.get_byte(addr) -> Byte)
+    }
+
+    fn get_symbol(&self, addr: Self::Size) -> Result<Self::Symbol, Self::Error> {
+        mock_call!(self.inner_ref.get_symbol(addr) -> Symbol)
+    }
+
+    fn get_expression(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_ref.get_expression(addr) -> Size)
+    }
+
+    fn get_external(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_ref.get_external(addr) -> Size)
+    }
+
+    fn get_pair(&self, addr: Self::Size) -> Result<(Self::Size, Self::Size), Self::Error> {
+        mock_call!(self.inner_ref.get_pair(addr) -> Pair)
+    }
+
+    fn get_concatenation(&self, addr: Self::Size) -> Result<(Self::Size, Self::Size), Self::Error> {
+        mock_call!(self.inner_ref.get_concatenation(addr) -> Pair)
+    }
+
+    fn get_range(&self, addr: Self::Size) -> Result<(Self::Size, Self::Size), Self::Error> {
+        mock_call!(self.inner_ref.get_range(addr) -> Pair)
+    }
+
+    fn get_slice(&self, addr: Self::Size) -> Result<(Self::Size, Self::Size), Self::Error> {
+        mock_call!(self.inner_ref.get_slice(addr) -> Pair)
+    }
+
+    fn get_list_len(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_ref.get_list_len(addr) -> Size)
+    }
+
+    fn get_list_item(&self, list_addr: Self::Size, item_addr: Self::Number) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_ref.get_list_item(list_addr, item_addr) -> Size)
+    }
+
+    fn get_list_associations_len(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_ref.get_list_associations_len(addr) -> Size)
+    }
+
+    fn get_list_association(&self, list_addr: Self::Size, item_addr: Self::Number) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_ref.get_list_association(list_addr, item_addr) -> Size)
+    }
+
+    fn get_list_item_with_symbol(&self, list_addr: Self::Size, sym: Self::Symbol) -> Result<Option<Self::Size>, Self::Error> {
+        mock_call!(self.inner_ref.get_list_item_with_symbol(list_addr, sym) -> OptionalSize)
+    }
+
+    fn get_list_items_iter(&self, list_addr: Self::Size) -> Self::ListIndexIterator {
+        mock_call!(self.inner_ref.get_list_items_iter(list_addr))
+    }
+
+    fn get_list_associations_iter(&self, list_addr: Self::Size) -> Self::ListIndexIterator {
+        mock_call!(self.inner_ref.get_list_associations_iter(list_addr))
+    }
+
+    fn get_char_list_len(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_ref.get_char_list_len(addr) -> Size)
+    }
+
+    fn get_char_list_item(&self, addr: Self::Size, item_index: Self::Number) -> Result<Self::Char, Self::Error> {
+        mock_call!(self.inner_ref.get_char_list_item(addr, item_index) -> Char)
+    }
+
+    fn get_char_list_iter(&self, list_addr: Self::Size) -> Self::ListIndexIterator {
+        mock_call!(self.inner_ref.get_char_list_iter(list_addr))
+    }
+
+    fn get_byte_list_len(&self, addr: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_ref.get_byte_list_len(addr) -> Size)
+    }
+
+    fn get_byte_list_item(&self, addr: Self::Size, item_index: Self::Number) -> Result<Self::Byte, Self::Error> {
+        mock_call!(self.inner_ref.get_byte_list_item(addr, item_index) -> Byte)
+    }
+
+    fn get_byte_list_iter(&self, list_addr: Self::Size) -> Self::ListIndexIterator {
+        mock_call!(self.inner_ref.get_byte_list_iter(list_addr))
+    }
+
+    fn add_unit(&mut self) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_unit() -> Size)
+    }
+
+    fn add_true(&mut self) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_true() -> Size)
+    }
+
+    fn add_false(&mut self) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_false() -> Size)
+    }
+
+    fn add_number(&mut self, value: Self::Number) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_number(value) -> Size)
+    }
+
+    fn add_type(&mut self, value: GarnishDataType) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_type(value) -> Size)
+    }
+
+    fn add_char(&mut self, value: Self::Char) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_char(value) -> Size)
+    }
+
+    fn add_byte(&mut self, value: Self::Byte) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_byte(value) -> Size)
+    }
+
+    fn add_symbol(&mut self, value: Self::Symbol) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_symbol(value) -> Size)
+    }
+
+    fn add_expression(&mut self, value: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_expression(value) -> Size)
+    }
+
+    fn add_external(&mut self, value: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_external(value) -> Size)
+    }
+
+    fn add_pair(&mut self, value: (Self::Size, Self::Size)) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_pair(value) -> Size)
+    }
+
+    fn add_concatenation(&mut self, left: Self::Size, right: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_concatenation(left, right) -> Size)
+    }
+
+    fn add_range(&mut self, start: Self::Size, end: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_range(start, end) -> Size)
+    }
+
+    fn add_slice(&mut self, list: Self::Size, range: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_slice(list, range) -> Size)
+    }
+
+    fn start_list(&mut self, len: Self::Size) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.start_list(len) -> Ok)
+    }
+
+    fn add_to_list(&mut self, addr: Self::Size, is_associative: bool) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.add_to_list(addr, is_associative) -> Ok)
+    }
+
+    fn end_list(&mut self) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.end_list() -> Size)
+    }
+
+    fn start_char_list(&mut self) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.start_char_list() -> Ok)
+    }
+
+    fn add_to_char_list(&mut self, c: Self::Char) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.add_to_char_list(c) -> Ok)
+    }
+
+    fn end_char_list(&mut self) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.end_char_list() -> Size)
+    }
+
+    fn start_byte_list(&mut self) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.start_byte_list() -> Ok)
+    }
+
+    fn add_to_byte_list(&mut self, c: Self::Byte) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.add_to_byte_list(c) -> Ok)
+    }
+
+    fn end_byte_list(&mut self) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.end_byte_list() -> Size)
+    }
+
+    fn get_register_len(&self) -> Self::Size {
+        mock_call!(self.inner_ref.get_register_len() => Size)
+    }
+
+    fn push_register(&mut self, addr: Self::Size) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.push_register(addr) -> Ok)
+    }
+
+    fn get_register(&self, addr: Self::Size) -> Option<Self::Size> {
+        mock_call!(self.inner_ref.get_register(addr) => OptionalSize)
+    }
+
+    fn pop_register(&mut self) -> Result<Option<Self::Size>, Self::Error> {
+        mock_call!(self.inner_mut_ref.pop_register() -> OptionalSize)
+    }
+
+    fn get_register_iter(&self) -> Self::RegisterIndexIterator {
+        mock_call!(self.inner_ref.get_register_iter())
+    }
+
+    fn get_instruction_len(&self) -> Self::Size {
+        mock_call!(self.inner_ref.get_instruction_len() => Size)
+    }
+
+    fn push_instruction(&mut self, instruction: Instruction, data: Option<Self::Size>) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.push_instruction(instruction, data) -> Size)
+    }
+
+    fn get_instruction(&self, addr: Self::Size) -> Option<(Instruction, Option<Self::Size>)> {
+        mock_call!(self.inner_ref.get_instruction(addr) => Instruction)
+    }
+
+    fn get_instruction_iter(&self) -> Self::InstructionIterator {
+        mock_call!(self.inner_ref.get_instruction_iter())
+    }
+
+    fn get_instruction_cursor(&self) -> Self::Size {
+        mock_call!(self.inner_ref.get_instruction_cursor() => Size)
+    }
+
+    fn set_instruction_cursor(&mut self, addr: Self::Size) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.set_instruction_cursor(addr) -> Ok)
+    }
+
+    fn get_jump_table_len(&self) -> Self::Size {
+        mock_call!(self.inner_ref.get_jump_table_len() => Size)
+    }
+
+    fn push_jump_point(&mut self, index: Self::Size) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.push_jump_point(index) -> Ok)
+    }
+
+    fn get_jump_point(&self, index: Self::Size) -> Option<Self::Size> {
+        mock_call!(self.inner_ref.get_jump_point(index) => OptionalSize)
+    }
+
+    fn get_jump_point_mut(&mut self, index: Self::Size) -> Option<&mut Self::Size> {
+        mock_call!(self.inner_mut_ref.get_jump_point_mut(index))
+    }
+
+    fn get_jump_table_iter(&self) -> Self::JumpTableIndexIterator {
+        mock_call!(self.inner_ref.get_jump_table_iter())
+    }
+
+    fn push_jump_path(&mut self, index: Self::Size) -> Result<(), Self::Error> {
+        mock_call!(self.inner_mut_ref.push_jump_path(index) -> Ok)
+    }
+
+    fn pop_jump_path(&mut self) -> Option<Self::Size> {
+        mock_call!(self.inner_mut_ref.pop_jump_path() => OptionalSize)
+    }
+
+    fn get_jump_path_iter(&self) -> Self::JumpPathIndexIterator {
+        mock_call!(self.inner_ref.get_jump_path_iter())
+    }
+
+    fn size_to_number(from: Self::Size) -> Self::Number {
+        Inner::size_to_number(from)
+    }
+
+    fn number_to_size(from: Self::Number) -> Option<Self::Size> {
+        Inner::number_to_size(from)
+    }
+
+    fn number_to_char(from: Self::Number) -> Option<Self::Char> {
+        Inner::number_to_char(from)
+    }
+
+    fn number_to_byte(from: Self::Number) -> Option<Self::Byte> {
+        Inner::number_to_byte(from)
+    }
+
+    fn char_to_number(from: Self::Char) -> Option<Self::Number> {
+        Inner::char_to_number(from)
+    }
+
+    fn char_to_byte(from: Self::Char) -> Option<Self::Byte> {
+        Inner::char_to_byte(from)
+    }
+
+    fn byte_to_number(from: Self::Byte) -> Option<Self::Number> {
+        Inner::byte_to_number(from)
+    }
+
+    fn byte_to_char(from: Self::Byte) -> Option<Self::Char> {
+        Inner::byte_to_char(from)
+    }
+
+    fn add_char_list_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_char_list_from(from) -> Size)
+    }
+
+    fn add_byte_list_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_byte_list_from(from) -> Size)
+    }
+
+    fn add_symbol_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_symbol_from(from) -> Size)
+    }
+
+    fn add_byte_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_byte_from(from) -> Size)
+    }
+
+    fn add_number_from(&mut self, from: Self::Size) -> Result<Self::Size, Self::Error> {
+        mock_call!(self.inner_mut_ref.add_number_from(from) -> Size)
+    }
+
+    fn parse_number(from: &str) -> Result<Self::Number, Self::Error> {
+        Inner::parse_number(from)
+    }
+
+    fn parse_symbol(from: &str) -> Result<Self::Symbol, Self::Error> {
+        Inner::parse_symbol(from)
+    }
+
+    fn parse_char(from: &str) -> Result<Self::Char, Self::Error> {
+        Inner::parse_char(from)
+    }
+
+    fn parse_byte(from: &str) -> Result<Self::Byte, Self::Error> {
+        Inner::parse_byte(from)
+    }
+
+    fn parse_char_list(from: &str) -> Result<Vec<Self::Char>, Self::Error> {
+        Inner::parse_char_list(from)
+    }
+
+    fn parse_byte_list(from: &str) -> Result<Vec<Self::Byte>, Self::Error> {
+        Inner::parse_byte_list(from)
+    }
+
+    fn make_size_iterator_range(min: Self::Size, max: Self::Size) -> Self::SizeIterator {
+        Inner::make_size_iterator_range(min, max)
+    }
+
+    fn make_number_iterator_range(min: Self::Number, max: Self::Number) -> Self::NumberIterator {
+        Inner::make_number_iterator_range(min, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{DataError, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, GarnishDataType};
+    use crate::mock::{MockCall, MockData, MockResponse};
+
+    #[test]
+    fn records_calls() {
+        let mut data = MockData::wrap(SimpleGarnishData::new());
+
+        let addr = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        data.get_number(addr).unwrap();
+
+        assert_eq!(
+            data.calls(),
+            vec![
+                MockCall { method: "add_number", args: vec!["Integer(10)".to_string()] },
+                MockCall { method: "get_number", args: vec![addr.to_string()] },
+            ]
+        );
+        assert_eq!(data.calls()[0].to_string(), "add_number(Integer(10))");
+    }
+
+    #[test]
+    fn scripted_response() {
+        let mut data = MockData::<SimpleGarnishData>::new();
+        data.respond("get_data_type", MockResponse::DataType(GarnishDataType::Symbol));
+        data.respond_nth("add_unit", 1, MockResponse::Size(20));
+        data.respond("add_unit", MockResponse::Size(10));
+
+        assert_eq!(data.get_data_type(5).unwrap(), GarnishDataType::Symbol);
+        assert_eq!(data.add_unit().unwrap(), 10);
+        assert_eq!(data.add_unit().unwrap(), 20);
+        assert_eq!(data.add_unit().unwrap(), 10);
+        assert_eq!(data.call_count("add_unit"), 3);
+    }
+
+    #[test]
+    fn injected_error() {
+        let mut data = MockData::wrap(SimpleGarnishData::new());
+        data.fail_nth("add_unit", 1, DataError::from("injected".to_string()));
+
+        assert!(data.add_unit().is_ok());
+        assert_eq!(data.add_unit().unwrap_err().to_string(), "injected");
+        assert!(data.add_unit().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "MockData has no response for add_true and no inner data")]
+    fn no_response() {
+        let mut data = MockData::<SimpleGarnishData>::new();
+        data.add_true().unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "MockData can't return Size response from get_data_type")]
+    fn wrong_response() {
+        let mut data = MockData::<SimpleGarnishData>::new();
+        data.respond("get_data_type", MockResponse::Size(10));
+        data.get_data_type(0).unwrap();
+    }
+}
@@ -0,0 +1,14 @@
+use garnish_lang_traits::GarnishData;
+
+/// Converts an integer to the data's number type with [`GarnishData::parse_number`].
+///
+/// Used everywhere the crate needs a number it didn't read from the data, so all conversions agree.
+pub(crate) fn number_from_u64<Data: GarnishData>(value: u64) -> Result<Data::Number, Data::Error> {
+    Data::parse_number(&value.to_string())
+}
+
+/// Converts an integer to the data's size type, going through [`number_from_u64`].
+#[cfg(any(test, feature = "testing"))]
+pub(crate) fn size_from_u64<Data: GarnishData>(value: u64) -> Result<Option<Data::Size>, Data::Error> {
+    Ok(Data::number_to_size(number_from_u64::<Data>(value)?))
+}
@@ -0,0 +1,336 @@
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::number::number_from_u64;
+use crate::{get_association, list_items, read_string, UtilityError};
+
+/// Literal that can be compared with a value in a [`GarnishData`] implementation, used by [`garnish_match!`](crate::garnish_match).
+pub trait PatternLiteral {
+    fn matches<Data: GarnishData>(&self, addr: Data::Size, data: &Data) -> Result<bool, UtilityError<Data::Error>>;
+}
+
+/// Matches a CharList with the same characters.
+impl PatternLiteral for &str {
+    fn matches<Data: GarnishData>(&self, addr: Data::Size, data: &Data) -> Result<bool, UtilityError<Data::Error>> {
+        match data.get_data_type(addr.clone())? {
+            GarnishDataType::CharList => Ok(read_string(addr, data)? == *self),
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Matches a Char with the same code point.
+impl PatternLiteral for char {
+    fn matches<Data: GarnishData>(&self, addr: Data::Size, data: &Data) -> Result<bool, UtilityError<Data::Error>> {
+        match data.get_data_type(addr.clone())? {
+            GarnishDataType::Char => Ok(Data::char_to_number(data.get_char(addr)?) == Some(number_from_u64::<Data>(*self as u64)?)),
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Matches True or False.
+impl PatternLiteral for bool {
+    fn matches<Data: GarnishData>(&self, addr: Data::Size, data: &Data) -> Result<bool, UtilityError<Data::Error>> {
+        Ok(match data.get_data_type(addr)? {
+            GarnishDataType::True => *self,
+            GarnishDataType::False => !*self,
+            _ => false,
+        })
+    }
+}
+
+macro_rules! number_literal {
+    ($format:literal, $($t:ty),*) => {
+        $(
+            /// Matches a Number equal to the literal parsed with [`GarnishData::parse_number`].
+            impl PatternLiteral for $t {
+                fn matches<Data: GarnishData>(&self, addr: Data::Size, data: &Data) -> Result<bool, UtilityError<Data::Error>> {
+                    match data.get_data_type(addr.clone())? {
+                        GarnishDataType::Number => Ok(data.get_number(addr)? == Data::parse_number(&format!($format, self))?),
+                        _ => Ok(false),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+number_literal!("{}", i32, i64, u32, u64);
+// debug formatting keeps the decimal point of whole floats, so 5.0 parses as a float and not an integer
+number_literal!("{:?}", f64);
+
+pub fn match_unit<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<bool, UtilityError<Data::Error>> {
+    Ok(data.get_data_type(addr)? == GarnishDataType::Unit)
+}
+
+pub fn match_literal<Data: GarnishData, Literal: PatternLiteral>(
+    addr: Data::Size,
+    data: &Data,
+    literal: Literal,
+) -> Result<bool, UtilityError<Data::Error>> {
+    literal.matches(addr, data)
+}
+
+/// Two addresses making up a Pair or Range.
+type Parts<Data> = (<Data as GarnishData>::Size, <Data as GarnishData>::Size);
+
+/// Left and right of a Pair, or [`None`] for any other type.
+pub fn match_pair<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Option<Parts<Data>>, UtilityError<Data::Error>> {
+    match data.get_data_type(addr.clone())? {
+        GarnishDataType::Pair => Ok(Some(data.get_pair(addr)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Start and end of a Range, or [`None`] for any other type.
+pub fn match_range<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Option<Parts<Data>>, UtilityError<Data::Error>> {
+    match data.get_data_type(addr.clone())? {
+        GarnishDataType::Range => Ok(Some(data.get_range(addr)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Items of a List, Concatenation or Slice as read by [`list_items`], or [`None`] for any other type.
+pub fn match_items<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Option<Vec<Data::Size>>, UtilityError<Data::Error>> {
+    match data.get_data_type(addr.clone())? {
+        GarnishDataType::List | GarnishDataType::Concatenation | GarnishDataType::Slice => Ok(Some(list_items(addr, data)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Value of the association with the named symbol, or [`None`] if the value isn't list-like or has no such association.
+pub fn match_association<Data: GarnishData>(addr: Data::Size, key: &str, data: &Data) -> Result<Option<Data::Size>, UtilityError<Data::Error>> {
+    match data.get_data_type(addr.clone())? {
+        GarnishDataType::List | GarnishDataType::Concatenation | GarnishDataType::Slice => {
+            get_association(addr, Data::parse_symbol(key)?, data)
+        }
+        _ => Ok(None),
+    }
+}
+
+#[doc(hidden)]
+pub fn match_result<Data: GarnishData, T>(
+    _data: &Data,
+    result: Result<Option<T>, UtilityError<Data::Error>>,
+) -> Result<Option<T>, UtilityError<Data::Error>> {
+    result
+}
+
+/// Matches a value against patterns in order, evaluating the first arm that matches with the addresses of the matched parts bound.
+///
+/// Evaluates to `Result<Option<T>, UtilityError<Data::Error>>`, with [`None`] when no arm matches.
+///
+/// Patterns:
+/// - `_` matches anything and `name` matches anything, binding its address
+/// - `()` matches Unit, and string, char, bool and number literals match a CharList, Char, True or False, or Number (see [`PatternLiteral`])
+/// - `(left = right)` matches a Pair and `(start .. end)` matches a Range
+/// - `[a, b]` matches a List, Concatenation or Slice with exactly those items, and `[a, b, ..rest]` binds the remaining item addresses to a [`Vec`]
+/// - `{key: pattern, other}` matches a list-like value with those associations, where `other` is shorthand for `other: other`
+///
+/// ```ignore
+/// let name = garnish_match!(*data, addr, {
+///     (name = _) => Some(name),
+///     {name, age: 30} => Some(name),
+///     [first, ..rest] => Some(first),
+///     "anonymous" => None,
+/// })?;
+/// ```
+#[macro_export]
+macro_rules! garnish_match {
+    ($data:expr, $addr:expr, { $($pattern:tt => $body:expr),* $(,)? }) => {{
+        let __data = &$data;
+        let __addr = $addr;
+        #[allow(unreachable_code)]
+        let result = 'garnish_match: {
+            $(
+                #[allow(unused_labels)]
+                'arm: {
+                    $crate::__garnish_pattern!(__data, __addr.clone(), 'arm, 'garnish_match, $pattern, {
+                        break 'garnish_match Ok(Some($body));
+                    });
+                }
+            )*
+            Ok(None)
+        };
+        $crate::match_result(__data, result)
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __garnish_pattern {
+    (@list $data:ident, $items:ident, $arm:lifetime, $outer:lifetime, [], $body:block) => {
+        match $items.next() {
+            Some(_) => break $arm,
+            None => $body,
+        }
+    };
+    (@list $data:ident, $items:ident, $arm:lifetime, $outer:lifetime, [.. $rest:ident $(,)?], $body:block) => {{
+        let $rest = $items.collect::<Vec<_>>();
+        $body
+    }};
+    (@list $data:ident, $items:ident, $arm:lifetime, $outer:lifetime, [$item:tt $(, $($more:tt)*)?], $body:block) => {
+        match $items.next() {
+            Some(item) => $crate::__garnish_pattern!($data, item, $arm, $outer, $item, {
+                $crate::__garnish_pattern!(@list $data, $items, $arm, $outer, [$($($more)*)?], $body)
+            }),
+            None => break $arm,
+        }
+    };
+    (@fields $data:ident, $record:ident, $arm:lifetime, $outer:lifetime, [], $body:block) => {
+        $body
+    };
+    (@fields $data:ident, $record:ident, $arm:lifetime, $outer:lifetime, [$key:ident : $pattern:tt $(, $($more:tt)*)?], $body:block) => {
+        match $crate::match_association($record.clone(), stringify!($key), $data) {
+            Ok(Some(value)) => $crate::__garnish_pattern!($data, value, $arm, $outer, $pattern, {
+                $crate::__garnish_pattern!(@fields $data, $record, $arm, $outer, [$($($more)*)?], $body)
+            }),
+            Ok(None) => break $arm,
+            Err(e) => break $outer Err(e),
+        }
+    };
+    (@fields $data:ident, $record:ident, $arm:lifetime, $outer:lifetime, [$key:ident $(, $($more:tt)*)?], $body:block) => {
+        $crate::__garnish_pattern!(@fields $data, $record, $arm, $outer, [$key: $key $(, $($more)*)?], $body)
+    };
+    ($data:ident, $addr:expr, $arm:lifetime, $outer:lifetime, _, $body:block) => {{
+        let _ = $addr;
+        $body
+    }};
+    ($data:ident, $addr:expr, $arm:lifetime, $outer:lifetime, (), $body:block) => {
+        match $crate::match_unit($addr, $data) {
+            Ok(true) => $body,
+            Ok(false) => break $arm,
+            Err(e) => break $outer Err(e),
+        }
+    };
+    ($data:ident, $addr:expr, $arm:lifetime, $outer:lifetime, ($left:tt = $right:tt), $body:block) => {
+        match $crate::match_pair($addr, $data) {
+            Ok(Some((left, right))) => $crate::__garnish_pattern!($data, left, $arm, $outer, $left, {
+                $crate::__garnish_pattern!($data, right, $arm, $outer, $right, $body)
+            }),
+            Ok(None) => break $arm,
+            Err(e) => break $outer Err(e),
+        }
+    };
+    ($data:ident, $addr:expr, $arm:lifetime, $outer:lifetime, ($start:tt .. $end:tt), $body:block) => {
+        match $crate::match_range($addr, $data) {
+            Ok(Some((start, end))) => $crate::__garnish_pattern!($data, start, $arm, $outer, $start, {
+                $crate::__garnish_pattern!($data, end, $arm, $outer, $end, $body)
+            }),
+            Ok(None) => break $arm,
+            Err(e) => break $outer Err(e),
+        }
+    };
+    ($data:ident, $addr:expr, $arm:lifetime, $outer:lifetime, [$($items:tt)*], $body:block) => {
+        match $crate::match_items($addr, $data) {
+            Ok(Some(items)) => {
+                let mut items = items.into_iter();
+                $crate::__garnish_pattern!(@list $data, items, $arm, $outer, [$($items)*], $body)
+            }
+            Ok(None) => break $arm,
+            Err(e) => break $outer Err(e),
+        }
+    };
+    ($data:ident, $addr:expr, $arm:lifetime, $outer:lifetime, {$($fields:tt)*}, $body:block) => {{
+        let record = $addr;
+        $crate::__garnish_pattern!(@fields $data, record, $arm, $outer, [$($fields)*], $body)
+    }};
+    ($data:ident, $addr:expr, $arm:lifetime, $outer:lifetime, $literal:literal, $body:block) => {
+        match $crate::match_literal($addr, $data, $literal) {
+            Ok(true) => $body,
+            Ok(false) => break $arm,
+            Err(e) => break $outer Err(e),
+        }
+    };
+    ($data:ident, $addr:expr, $arm:lifetime, $outer:lifetime, $name:ident, $body:block) => {{
+        let $name = $addr;
+        $body
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{DataError, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::GarnishData;
+    use crate::mock::MockData;
+    use crate::{match_literal, UtilityError, ValueBuilder};
+
+    fn describe(data: &SimpleGarnishData, addr: usize) -> Result<Option<String>, UtilityError<DataError>> {
+        garnish_match!(*data, addr, {
+            () => "unit".to_string(),
+            "literal" => "literal".to_string(),
+            5 => "five".to_string(),
+            {name, age: 30} => format!("record {}", name),
+            (key = (start .. 10)) => format!("pair {} with range from {}", key, start),
+            (_ = _) => "pair".to_string(),
+            [first, [inner], ..rest] => format!("list {} {} {}", first, inner, rest.len()),
+            [] => "empty".to_string(),
+        })
+    }
+
+    #[test]
+    fn match_values() {
+        let mut data = SimpleGarnishData::new();
+        let unit = data.add_unit().unwrap();
+        let literal = ValueBuilder::new(&mut data).chars("literal".chars()).unwrap();
+        let five = ValueBuilder::new(&mut data).number(5.into()).unwrap();
+        let record = ValueBuilder::new(&mut data)
+            .list(|l| l.assoc("name", |v| v.chars("someone".chars())).assoc("age", |v| v.number(30.into())))
+            .unwrap();
+        let pair = ValueBuilder::new(&mut data)
+            .pair(|v| v.symbol("key"), |v| v.range(|v| v.number(1.into()), |v| v.number(10.into())))
+            .unwrap();
+        let other_pair = ValueBuilder::new(&mut data).pair(|v| v.unit(), |v| v.unit()).unwrap();
+        let list = ValueBuilder::new(&mut data)
+            .list(|l| l.item(|v| v.number(1.into())).item(|v| v.list(|l| l.item(|v| v.number(2.into())))).items(3..6, |v, n| v.number(n.into())))
+            .unwrap();
+        let empty = ValueBuilder::new(&mut data).list(|l| l).unwrap();
+        let unmatched = data.add_true().unwrap();
+
+        let (name, _) = data.get_pair(data.get_list_item(record, 0.into()).unwrap()).unwrap();
+        let name_value = data.get_list_item_with_symbol(record, data.get_symbol(name).unwrap()).unwrap().unwrap();
+        let (key, range) = data.get_pair(pair).unwrap();
+        let (start, _) = data.get_range(range).unwrap();
+        let first = data.get_list_item(list, 0.into()).unwrap();
+        let inner = data.get_list_item(data.get_list_item(list, 1.into()).unwrap(), 0.into()).unwrap();
+
+        assert_eq!(describe(&data, unit).unwrap(), Some("unit".to_string()));
+        assert_eq!(describe(&data, literal).unwrap(), Some("literal".to_string()));
+        assert_eq!(describe(&data, five).unwrap(), Some("five".to_string()));
+        assert_eq!(describe(&data, record).unwrap(), Some(format!("record {}", name_value)));
+        assert_eq!(describe(&data, pair).unwrap(), Some(format!("pair {} with range from {}", key, start)));
+        assert_eq!(describe(&data, other_pair).unwrap(), Some("pair".to_string()));
+        assert_eq!(describe(&data, list).unwrap(), Some(format!("list {} {} 3", first, inner)));
+        assert_eq!(describe(&data, empty).unwrap(), Some("empty".to_string()));
+        assert_eq!(describe(&data, unmatched).unwrap(), None);
+    }
+
+    #[test]
+    fn match_number_and_char_literals() {
+        let mut data = SimpleGarnishData::new();
+        let integer = data.add_number(SimpleNumber::Integer(5)).unwrap();
+        let float = data.add_number(SimpleNumber::Float(5.0)).unwrap();
+        let fraction = data.add_number(SimpleNumber::Float(2.5)).unwrap();
+        let c = data.add_char('a').unwrap();
+
+        assert!(match_literal(integer, &data, 5).unwrap());
+        assert!(match_literal(float, &data, 5.0).unwrap());
+        assert!(match_literal(fraction, &data, 2.5).unwrap());
+        assert!(!match_literal(integer, &data, 6).unwrap());
+        assert!(match_literal(c, &data, 'a').unwrap());
+        assert!(!match_literal(c, &data, 'b').unwrap());
+    }
+
+    #[test]
+    fn match_error() {
+        let mut data = MockData::wrap(SimpleGarnishData::new());
+        let pair = ValueBuilder::new(&mut data).pair(|v| v.unit(), |v| v.unit()).unwrap();
+        data.fail("get_pair", DataError::from("failed".to_string()));
+
+        let result = garnish_match!(data, pair, {
+            (left = _) => left,
+            _ => pair,
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "failed");
+    }
+}
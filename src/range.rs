@@ -0,0 +1,234 @@
+use garnish_lang_traits::{GarnishData, GarnishDataType, GarnishNumber, TypeConstants};
+use crate::data::add_list;
+use crate::UtilityError;
+
+/// Type of the values a range covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeKind {
+    Number,
+    Char,
+    Byte,
+}
+
+/// Single value covered by a range.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RangeValue<Number, Char, Byte> {
+    Number(Number),
+    Char(Char),
+    Byte(Byte),
+}
+
+pub type DataRangeValue<Data> = RangeValue<<Data as GarnishData>::Number, <Data as GarnishData>::Char, <Data as GarnishData>::Byte>;
+
+enum RangeNumbers<Data: GarnishData> {
+    Bounded(Data::NumberIterator),
+    Open(Option<Data::Number>),
+}
+
+/// Iterator over the values covered by a range, including its end.
+///
+/// Values are stepped through as Numbers with [`GarnishData::make_number_iterator_range`] and converted back to the range's [`RangeKind`].
+/// Ranges with a Unit end are open and never end on their own.
+pub struct RangeIterator<Data: GarnishData> {
+    kind: RangeKind,
+    numbers: RangeNumbers<Data>,
+}
+
+impl<Data: GarnishData> RangeIterator<Data> {
+    /// Iterator for the Range at the given address.
+    ///
+    /// Start and end must be the same type of Number, Char or Byte, or Unit. A Unit start is treated as zero.
+    pub fn new(addr: Data::Size, data: &Data) -> Result<Self, UtilityError<Data::Error>> {
+        let (kind, start, end) = resolve_range(addr, data)?;
+
+        let numbers = match end {
+            None => RangeNumbers::Open(Some(start)),
+            Some(end) if end < start => RangeNumbers::Bounded(Data::make_number_iterator_range(start.clone(), start)),
+            Some(end) => match end.clone().increment() {
+                Some(after_end) => RangeNumbers::Bounded(Data::make_number_iterator_range(start, after_end)),
+                None => Err(UtilityError::InvalidRange(format!("end of range {} can't be stepped past", end)))?,
+            },
+        };
+
+        Ok(Self { kind, numbers })
+    }
+
+    pub fn kind(&self) -> RangeKind {
+        self.kind
+    }
+
+    fn convert(&self, number: Data::Number) -> Result<DataRangeValue<Data>, UtilityError<Data::Error>> {
+        match self.kind {
+            RangeKind::Number => Ok(RangeValue::Number(number)),
+            RangeKind::Char => match Data::number_to_char(number.clone()) {
+                Some(c) => Ok(RangeValue::Char(c)),
+                None => Err(UtilityError::InvalidChar(format!("{} in range can't be converted to a character", number))),
+            },
+            RangeKind::Byte => match Data::number_to_byte(number.clone()) {
+                Some(b) => Ok(RangeValue::Byte(b)),
+                None => Err(UtilityError::InvalidByte(format!("{} in range can't be converted to a byte", number))),
+            },
+        }
+    }
+}
+
+impl<Data: GarnishData> Iterator for RangeIterator<Data> {
+    type Item = Result<DataRangeValue<Data>, UtilityError<Data::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let number = match &mut self.numbers {
+            RangeNumbers::Bounded(iter) => iter.next()?,
+            RangeNumbers::Open(next) => {
+                let number = next.take()?;
+                *next = number.clone().increment();
+                number
+            }
+        };
+
+        Some(self.convert(number))
+    }
+}
+
+/// Number of values covered by the Range at the given address, or [`None`] if it's open.
+pub fn range_len<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Option<Data::Size>, UtilityError<Data::Error>> {
+    let (_, start, end) = resolve_range(addr.clone(), data)?;
+
+    let end = match end {
+        None => return Ok(None),
+        Some(end) if end < start => return Ok(Some(Data::Size::zero())),
+        Some(end) => end,
+    };
+
+    match end.subtract(start).and_then(|n| n.increment()).and_then(Data::number_to_size) {
+        Some(len) => Ok(Some(len)),
+        None => Err(UtilityError::InvalidRange(format!("length of range at {} can't be stored as a size", addr))),
+    }
+}
+
+/// Creates a new list of each value covered by the Range at the given address.
+///
+/// Fails with [`UtilityError::InvalidRange`] if the range has a Unit end.
+pub fn materialize_range<Data: GarnishData>(addr: Data::Size, data: &mut Data) -> Result<Data::Size, UtilityError<Data::Error>> {
+    if range_len(addr.clone(), data)?.is_none() {
+        Err(UtilityError::InvalidRange(format!("range at {} has no end", addr)))?;
+    }
+
+    let mut items = vec![];
+    for value in RangeIterator::new(addr, data)? {
+        items.push(match value? {
+            RangeValue::Number(n) => data.add_number(n)?,
+            RangeValue::Char(c) => data.add_char(c)?,
+            RangeValue::Byte(b) => data.add_byte(b)?,
+        });
+    }
+
+    Ok(add_list(items, data)?)
+}
+
+/// Kind of a range bound and its value as a Number.
+type RangeBound<Data> = (RangeKind, <Data as GarnishData>::Number);
+
+/// Kind, start and end of a range, with [`None`] for an open end.
+type ResolvedRange<Data> = (RangeKind, <Data as GarnishData>::Number, Option<<Data as GarnishData>::Number>);
+
+fn resolve_range<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<ResolvedRange<Data>, UtilityError<Data::Error>> {
+    let (start, end) = match data.get_data_type(addr.clone())? {
+        GarnishDataType::Range => data.get_range(addr.clone())?,
+        t => Err(UtilityError::UnsupportedType(format!("value at {} is {:?}, expected Range", addr, t)))?,
+    };
+
+    let start = range_bound(start, data)?;
+    let end = range_bound(end, data)?;
+
+    let kind = match (&start, &end) {
+        (Some((start_kind, _)), Some((end_kind, _))) if start_kind != end_kind => Err(UtilityError::UnsupportedType(format!(
+            "range at {} starts with {:?} and ends with {:?}",
+            addr, start_kind, end_kind
+        )))?,
+        (Some((kind, _)), _) | (None, Some((kind, _))) => *kind,
+        (None, None) => RangeKind::Number,
+    };
+
+    let start = start.map(|(_, n)| n).unwrap_or_else(Data::Number::zero);
+    Ok((kind, start, end.map(|(_, n)| n)))
+}
+
+fn range_bound<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Option<RangeBound<Data>>, UtilityError<Data::Error>> {
+    let (kind, number) = match data.get_data_type(addr.clone())? {
+        GarnishDataType::Unit => return Ok(None),
+        GarnishDataType::Number => (RangeKind::Number, Some(data.get_number(addr.clone())?)),
+        GarnishDataType::Char => (RangeKind::Char, Data::char_to_number(data.get_char(addr.clone())?)),
+        GarnishDataType::Byte => (RangeKind::Byte, Data::byte_to_number(data.get_byte(addr.clone())?)),
+        t => Err(UtilityError::UnsupportedType(format!("range bound at {} is {:?}, expected Number, Char, Byte or Unit", addr, t)))?,
+    };
+
+    match number {
+        Some(number) => Ok(Some((kind, number))),
+        None => Err(UtilityError::UnsupportedType(format!("range bound at {} can't be converted to a number", addr))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleGarnishData, SimpleNumber};
+    use crate::testing::GarnishValue;
+    use crate::{assert_garnish_eq, materialize_range, range_len, RangeIterator, RangeKind, RangeValue, UtilityError, ValueBuilder};
+
+    #[test]
+    fn numbers() {
+        let mut data = SimpleGarnishData::new();
+        let range = ValueBuilder::new(&mut data).range(|v| v.number(2.into()), |v| v.number(4.into())).unwrap();
+
+        let values = RangeIterator::new(range, &data).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                RangeValue::Number(SimpleNumber::Integer(2)),
+                RangeValue::Number(SimpleNumber::Integer(3)),
+                RangeValue::Number(SimpleNumber::Integer(4))
+            ]
+        );
+        assert_eq!(range_len(range, &data).unwrap(), Some(3));
+    }
+
+    #[test]
+    fn chars_materialized() {
+        let mut data = SimpleGarnishData::new();
+        let range = ValueBuilder::new(&mut data).range(|v| v.char('a'), |v| v.char('c')).unwrap();
+
+        let list = materialize_range(range, &mut data).unwrap();
+
+        assert_eq!(RangeIterator::new(range, &data).unwrap().kind(), RangeKind::Char);
+        assert_garnish_eq!(data, list, GarnishValue::List(vec![GarnishValue::Char('a'), GarnishValue::Char('b'), GarnishValue::Char('c')]));
+    }
+
+    #[test]
+    fn bytes_reversed_is_empty() {
+        let mut data = SimpleGarnishData::new();
+        let range = ValueBuilder::new(&mut data).range(|v| v.byte(5), |v| v.byte(1)).unwrap();
+
+        assert_eq!(RangeIterator::new(range, &data).unwrap().count(), 0);
+        assert_eq!(range_len(range, &data).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn open_range() {
+        let mut data = SimpleGarnishData::new();
+        let range = ValueBuilder::new(&mut data).range(|v| v.number(1.into()), |v| v.unit()).unwrap();
+
+        let values = RangeIterator::new(range, &data).unwrap().take(4).collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(values.last(), Some(&RangeValue::Number(SimpleNumber::Integer(4))));
+        assert_eq!(range_len(range, &data).unwrap(), None);
+        assert!(matches!(materialize_range(range, &mut data), Err(UtilityError::InvalidRange(_))));
+    }
+
+    #[test]
+    fn mixed_bounds() {
+        let mut data = SimpleGarnishData::new();
+        let range = ValueBuilder::new(&mut data).range(|v| v.number(1.into()), |v| v.char('c')).unwrap();
+
+        assert!(matches!(range_len(range, &data), Err(UtilityError::UnsupportedType(_))));
+    }
+}
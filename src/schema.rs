@@ -0,0 +1,350 @@
+use std::fmt::{Display, Formatter};
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::data::{extend, join, PathSegment};
+use crate::{get_association, list_items, UtilityError};
+
+/// Expected shape of a value, checked with [`validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schema {
+    /// Matches any value.
+    Any,
+    Type(GarnishDataType),
+    /// Unit or a value matching the inner schema.
+    Optional(Box<Schema>),
+    /// Value matching at least one of the schemas.
+    OneOf(Vec<Schema>),
+    /// List, Concatenation or Slice whose items all match the inner schema.
+    ListOf(Box<Schema>),
+    /// List, Concatenation or Slice with the given associations. Other items are allowed.
+    Record(Vec<Field>),
+    Pair(Box<Schema>, Box<Schema>),
+}
+
+impl Schema {
+    pub fn optional(schema: Schema) -> Self {
+        Schema::Optional(Box::new(schema))
+    }
+
+    pub fn one_of(schemas: Vec<Schema>) -> Self {
+        Schema::OneOf(schemas)
+    }
+
+    pub fn list_of(schema: Schema) -> Self {
+        Schema::ListOf(Box::new(schema))
+    }
+
+    pub fn record(fields: Vec<Field>) -> Self {
+        Schema::Record(fields)
+    }
+
+    pub fn pair(left: Schema, right: Schema) -> Self {
+        Schema::Pair(Box::new(left), Box::new(right))
+    }
+
+    /// Types a value can have to match the schema, or [`None`] if any type can.
+    fn accepted_types(&self) -> Option<Vec<GarnishDataType>> {
+        match self {
+            Schema::Any => None,
+            Schema::Type(t) => Some(vec![*t]),
+            Schema::Optional(schema) => schema.accepted_types().map(|types| add_types(vec![GarnishDataType::Unit], types)),
+            Schema::OneOf(schemas) => {
+                let mut types = vec![];
+                for schema in schemas {
+                    types = add_types(types, schema.accepted_types()?);
+                }
+                Some(types)
+            }
+            Schema::ListOf(_) | Schema::Record(_) => Some(vec![GarnishDataType::List, GarnishDataType::Concatenation, GarnishDataType::Slice]),
+            Schema::Pair(_, _) => Some(vec![GarnishDataType::Pair]),
+        }
+    }
+}
+
+/// Association of a [`Schema::Record`], by the name of its symbol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub key: String,
+    pub schema: Schema,
+    pub required: bool,
+}
+
+impl Field {
+    pub fn required(key: &str, schema: Schema) -> Self {
+        Field { key: key.to_string(), schema, required: true }
+    }
+
+    pub fn optional(key: &str, schema: Schema) -> Self {
+        Field { key: key.to_string(), schema, required: false }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// Value's type, found, isn't one of the expected types.
+    TypeMismatch(Vec<GarnishDataType>, GarnishDataType),
+    MissingKey(String),
+    /// Items couldn't be read, such as from a slice of a CharList or a concatenation that refers back to itself.
+    InvalidItems(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub path: Vec<PathSegment>,
+    pub kind: ViolationKind,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("$")?;
+        for segment in self.path.iter() {
+            write!(f, "{}", segment)?;
+        }
+
+        match &self.kind {
+            ViolationKind::TypeMismatch(expected, found) => {
+                let expected = expected.iter().map(|t| format!("{:?}", t)).collect::<Vec<String>>();
+                write!(f, ": expected {}, found {:?}", join(&expected, " or "), found)
+            }
+            ViolationKind::MissingKey(key) => write!(f, ": missing key {}", key),
+            ViolationKind::InvalidItems(message) => write!(f, ": invalid items, {}", message),
+        }
+    }
+}
+
+/// All violations found by [`validate`]. Displays one violation per line.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Validation {
+    pub violations: Vec<Violation>,
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Display for Validation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for violation in self.violations.iter() {
+            writeln!(f, "{}", violation)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the value at the given address against a schema, collecting every violation instead of stopping at the first.
+///
+/// Record keys are converted with [`GarnishData::parse_symbol`] and looked up with [`get_association`].
+/// For [`Schema::OneOf`], violations are reported from the first alternative accepting the value's type.
+pub fn validate<Data: GarnishData>(addr: Data::Size, data: &Data, schema: &Schema) -> Result<Validation, UtilityError<Data::Error>> {
+    let mut validation = Validation::default();
+    validate_internal(addr, data, schema, vec![], &mut validation.violations)?;
+    Ok(validation)
+}
+
+fn validate_internal<Data: GarnishData>(
+    addr: Data::Size,
+    data: &Data,
+    schema: &Schema,
+    path: Vec<PathSegment>,
+    violations: &mut Vec<Violation>,
+) -> Result<(), UtilityError<Data::Error>> {
+    let data_type = data.get_data_type(addr.clone())?;
+
+    if let Some(expected) = schema.accepted_types() {
+        if !expected.contains(&data_type) {
+            violations.push(Violation { path, kind: ViolationKind::TypeMismatch(expected, data_type) });
+            return Ok(());
+        }
+    }
+
+    match schema {
+        Schema::Any | Schema::Type(_) => {}
+        Schema::Optional(_) if data_type == GarnishDataType::Unit => {}
+        Schema::Optional(schema) => validate_internal(addr, data, schema, path, violations)?,
+        Schema::OneOf(schemas) => {
+            let mut first = None;
+            for schema in schemas {
+                if schema.accepted_types().is_some_and(|types| !types.contains(&data_type)) {
+                    continue;
+                }
+
+                let mut alternative = vec![];
+                validate_internal(addr.clone(), data, schema, path.clone(), &mut alternative)?;
+                if alternative.is_empty() {
+                    return Ok(());
+                }
+
+                first.get_or_insert(alternative);
+            }
+
+            violations.extend(first.unwrap_or_default());
+        }
+        Schema::ListOf(schema) => {
+            let items = match items_or_violation(addr, data, &path, violations)? {
+                Some(items) => items,
+                None => return Ok(()),
+            };
+
+            for (i, item) in items.into_iter().enumerate() {
+                validate_internal(item, data, schema, extend(&path, PathSegment::Index(i)), violations)?;
+            }
+        }
+        Schema::Record(fields) => {
+            // get_association reads the same items, so check they can be read first
+            if items_or_violation(addr.clone(), data, &path, violations)?.is_none() {
+                return Ok(());
+            }
+
+            for field in fields {
+                let key = Data::parse_symbol(&field.key)?;
+                match get_association(addr.clone(), key, data)? {
+                    Some(value) => validate_internal(value, data, &field.schema, extend(&path, PathSegment::Key(field.key.clone())), violations)?,
+                    None if field.required => violations.push(Violation { path: path.clone(), kind: ViolationKind::MissingKey(field.key.clone()) }),
+                    None => {}
+                }
+            }
+        }
+        Schema::Pair(left, right) => {
+            let (left_addr, right_addr) = data.get_pair(addr)?;
+            validate_internal(left_addr, data, left, extend(&path, PathSegment::Left), violations)?;
+            validate_internal(right_addr, data, right, extend(&path, PathSegment::Right), violations)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Items of the list, or [`None`] after adding a violation if the value has the right type but its items can't be read.
+fn items_or_violation<Data: GarnishData>(
+    addr: Data::Size,
+    data: &Data,
+    path: &[PathSegment],
+    violations: &mut Vec<Violation>,
+) -> Result<Option<Vec<Data::Size>>, UtilityError<Data::Error>> {
+    match list_items(addr, data) {
+        Ok(items) => Ok(Some(items)),
+        Err(e @ (UtilityError::UnsupportedType(_) | UtilityError::Cycle(_))) => {
+            violations.push(Violation { path: path.to_vec(), kind: ViolationKind::InvalidItems(e.to_string()) });
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn add_types(mut types: Vec<GarnishDataType>, other: Vec<GarnishDataType>) -> Vec<GarnishDataType> {
+    for t in other {
+        if !types.contains(&t) {
+            types.push(t);
+        }
+    }
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleData, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, GarnishDataType};
+    use crate::data::PathSegment;
+    use crate::{validate, Field, Schema, Violation, ViolationKind, ValueBuilder};
+
+    fn user_schema() -> Schema {
+        Schema::record(vec![
+            Field::required("name", Schema::Type(GarnishDataType::CharList)),
+            Field::required("age", Schema::Type(GarnishDataType::Number)),
+            Field::optional("email", Schema::optional(Schema::Type(GarnishDataType::CharList))),
+            Field::optional("tags", Schema::list_of(Schema::one_of(vec![Schema::Type(GarnishDataType::Symbol), Schema::Type(GarnishDataType::CharList)]))),
+        ])
+    }
+
+    #[test]
+    fn valid_record() {
+        let mut data = SimpleGarnishData::new();
+        let user = ValueBuilder::new(&mut data)
+            .list(|l| {
+                l.assoc("name", |v| v.chars("someone".chars()))
+                    .assoc("age", |v| v.number(30.into()))
+                    .assoc("email", |v| v.unit())
+                    .assoc("tags", |v| v.list(|l| l.item(|v| v.symbol("admin")).item(|v| v.chars("other".chars()))))
+            })
+            .unwrap();
+
+        let validation = validate(user, &data, &user_schema()).unwrap();
+
+        assert!(validation.is_valid(), "{}", validation);
+    }
+
+    #[test]
+    fn all_violations_with_paths() {
+        let mut data = SimpleGarnishData::new();
+        let user = ValueBuilder::new(&mut data)
+            .list(|l| {
+                l.assoc("name", |v| v.number(1.into()))
+                    .assoc("tags", |v| v.list(|l| l.item(|v| v.symbol("admin")).item(|v| v.number(2.into()))))
+            })
+            .unwrap();
+
+        let validation = validate(user, &data, &user_schema()).unwrap();
+
+        assert_eq!(
+            validation.violations,
+            vec![
+                Violation {
+                    path: vec![PathSegment::Key("name".to_string())],
+                    kind: ViolationKind::TypeMismatch(vec![GarnishDataType::CharList], GarnishDataType::Number),
+                },
+                Violation { path: vec![], kind: ViolationKind::MissingKey("age".to_string()) },
+                Violation {
+                    path: vec![PathSegment::Key("tags".to_string()), PathSegment::Index(1)],
+                    kind: ViolationKind::TypeMismatch(vec![GarnishDataType::Symbol, GarnishDataType::CharList], GarnishDataType::Number),
+                },
+            ]
+        );
+        assert_eq!(
+            validation.to_string(),
+            "$.name: expected CharList, found Number\n$: missing key age\n$.tags[1]: expected Symbol or CharList, found Number\n"
+        );
+    }
+
+    #[test]
+    fn union_reports_matching_alternative() {
+        let mut data = SimpleGarnishData::new();
+        let pair = ValueBuilder::new(&mut data).pair(|v| v.number(1.into()), |v| v.unit()).unwrap();
+        let schema = Schema::one_of(vec![
+            Schema::Type(GarnishDataType::Number),
+            Schema::pair(Schema::Type(GarnishDataType::Number), Schema::Type(GarnishDataType::CharList)),
+        ]);
+
+        let validation = validate(pair, &data, &schema).unwrap();
+
+        assert_eq!(validation.to_string(), "$.right: expected CharList, found Unit\n");
+    }
+
+    #[test]
+    fn unreadable_items_are_violations() {
+        let mut data = SimpleGarnishData::new();
+        let chars = data.parse_add_char_list("\"abc\"").unwrap();
+        let start = data.add_number(SimpleNumber::Integer(0)).unwrap();
+        let end = data.add_number(SimpleNumber::Integer(1)).unwrap();
+        let range = data.add_range(start, end).unwrap();
+        let slice = data.add_slice(chars, range).unwrap();
+        let cycle = data.get_data_len();
+        data.get_data_mut().push(SimpleData::Concatenation(start, cycle));
+        let record = ValueBuilder::new(&mut data)
+            .list(|l| l.assoc("name", |v| v.chars("someone".chars())).assoc("age", |v| v.number(30.into())).assoc("tags", |v| v.value(slice)))
+            .unwrap();
+        let schema = Schema::list_of(Schema::Any);
+
+        let record_validation = validate(record, &data, &user_schema()).unwrap();
+        let cycle_validation = validate(cycle, &data, &schema).unwrap();
+        let record_of_cycle = validate(cycle, &data, &user_schema()).unwrap();
+
+        assert_eq!(record_validation.violations.len(), 1);
+        assert_eq!(record_validation.violations[0].path, vec![PathSegment::Key("tags".to_string())]);
+        assert!(matches!(record_validation.violations[0].kind, ViolationKind::InvalidItems(_)));
+        assert_eq!(cycle_validation.violations.len(), 1);
+        assert!(cycle_validation.to_string().starts_with("$: invalid items, Cycle: "));
+        assert!(matches!(record_of_cycle.violations[..], [Violation { kind: ViolationKind::InvalidItems(_), .. }]));
+    }
+}
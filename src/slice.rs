@@ -0,0 +1,305 @@
+use garnish_lang_traits::{GarnishData, GarnishDataType, GarnishNumber, TypeConstants};
+use crate::address_set::AddressSet;
+use crate::data::add_list;
+use crate::{iterate_concatentation_checked, ConcatenationIterator, CyclePolicy, UtilityError};
+
+/// Single item covered by a slice.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SliceItem<Size, Char, Byte> {
+    /// Address of an item of a List or Concatenation.
+    Item(Size),
+    Char(Char),
+    Byte(Byte),
+}
+
+pub type DataSliceItem<Data> = SliceItem<<Data as GarnishData>::Size, <Data as GarnishData>::Char, <Data as GarnishData>::Byte>;
+
+enum SliceSource<'a, Data: GarnishData> {
+    List(Data::Size, Data::Number),
+    CharList(Data::Size, Data::Number),
+    ByteList(Data::Size, Data::Number),
+    Concatenation(ConcatenationIterator<'a, Data>),
+}
+
+/// Iterator over the items a Slice covers, read directly from the sliced value.
+///
+/// The range is inclusive and its bounds must be Numbers or Unit, with a Unit start or end covering the beginning or end of the value.
+/// Slices of slices are resolved to the innermost value.
+pub struct SliceIterator<'a, Data: GarnishData> {
+    data: &'a Data,
+    source_type: GarnishDataType,
+    source: SliceSource<'a, Data>,
+    index: Data::Number,
+    start: Data::Number,
+    end: Option<Data::Number>,
+    finished: bool,
+}
+
+impl<'a, Data: GarnishData> SliceIterator<'a, Data> {
+    pub fn new(addr: Data::Size, data: &'a Data) -> Result<Self, UtilityError<Data::Error>> {
+        let mut seen = AddressSet::new();
+        let mut start = Data::Number::zero();
+        let mut end: Option<Data::Number> = None;
+        let mut value = addr;
+
+        while data.get_data_type(value.clone())? == GarnishDataType::Slice {
+            if !seen.insert(value.clone()) {
+                return Err(UtilityError::Cycle(format!("slice at {} is its own value", value)));
+            }
+
+            let (inner, range) = data.get_slice(value.clone())?;
+            let (range_start, range_end) = slice_range(range, data)?;
+
+            // bounds of an outer slice are relative to the start of the inner one
+            let outer_end = end.map(|end| offset::<Data>(range_start.clone(), end)).transpose()?;
+            end = match (range_end, outer_end) {
+                (Some(inner_end), Some(outer_end)) if inner_end < outer_end => Some(inner_end),
+                (inner_end, None) => inner_end,
+                (_, outer_end) => outer_end,
+            };
+            start = offset::<Data>(range_start, start)?;
+            value = inner;
+        }
+
+        let source_type = data.get_data_type(value.clone())?;
+        let source = match source_type {
+            GarnishDataType::List => SliceSource::List(value.clone(), Data::size_to_number(data.get_list_len(value)?)),
+            GarnishDataType::CharList => SliceSource::CharList(value.clone(), Data::size_to_number(data.get_char_list_len(value)?)),
+            GarnishDataType::ByteList => SliceSource::ByteList(value.clone(), Data::size_to_number(data.get_byte_list_len(value)?)),
+            GarnishDataType::Concatenation => {
+                iterate_concatentation_checked(value.clone(), data, CyclePolicy::Error, |_| ())?;
+                SliceSource::Concatenation(ConcatenationIterator::new(value, data))
+            }
+            t => Err(UtilityError::UnsupportedType(format!(
+                "sliced value at {} is {:?}, expected List, CharList, ByteList or Concatenation",
+                value, t
+            )))?,
+        };
+
+        let index = match source {
+            SliceSource::Concatenation(_) => Data::Number::zero(),
+            _ => start.clone(),
+        };
+
+        Ok(Self { data, source_type, source, index, start, end, finished: false })
+    }
+
+    /// Type of the value the slice reads from.
+    pub fn source_type(&self) -> GarnishDataType {
+        self.source_type
+    }
+
+    fn take_next(&mut self) -> Result<Option<DataSliceItem<Data>>, Data::Error> {
+        loop {
+            if self.finished || self.end.as_ref().is_some_and(|end| &self.index > end) {
+                return Ok(None);
+            }
+
+            let index = self.index.clone();
+            self.index = match index.clone().increment() {
+                Some(next) => next,
+                None => return Ok(None),
+            };
+
+            let item = match &mut self.source {
+                SliceSource::List(_, len) | SliceSource::CharList(_, len) | SliceSource::ByteList(_, len) if &index >= len => {
+                    return Ok(None)
+                }
+                SliceSource::List(addr, _) => SliceItem::Item(self.data.get_list_item(addr.clone(), index)?),
+                SliceSource::CharList(addr, _) => SliceItem::Char(self.data.get_char_list_item(addr.clone(), index)?),
+                SliceSource::ByteList(addr, _) => SliceItem::Byte(self.data.get_byte_list_item(addr.clone(), index)?),
+                SliceSource::Concatenation(iter) => match iter.next().transpose()? {
+                    None => return Ok(None),
+                    Some(_) if index < self.start => continue,
+                    Some(item) => SliceItem::Item(item),
+                },
+            };
+
+            return Ok(Some(item));
+        }
+    }
+}
+
+impl<'a, Data: GarnishData> Iterator for SliceIterator<'a, Data> {
+    type Item = Result<DataSliceItem<Data>, Data::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.take_next() {
+            Ok(item) => item.map(Ok),
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Adds a new value of the items the Slice at the given address covers.
+///
+/// Slices of a CharList or ByteList produce a CharList or ByteList, and slices of a List or Concatenation produce a List.
+pub fn resolve_slice<Data: GarnishData>(addr: Data::Size, data: &mut Data) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let iter = SliceIterator::new(addr, data)?;
+    let source_type = iter.source_type();
+    let items = iter.collect::<Result<Vec<DataSliceItem<Data>>, Data::Error>>()?;
+
+    match source_type {
+        GarnishDataType::CharList => {
+            data.start_char_list()?;
+            for item in items {
+                if let SliceItem::Char(c) = item {
+                    data.add_to_char_list(c)?;
+                }
+            }
+            Ok(data.end_char_list()?)
+        }
+        GarnishDataType::ByteList => {
+            data.start_byte_list()?;
+            for item in items {
+                if let SliceItem::Byte(b) = item {
+                    data.add_to_byte_list(b)?;
+                }
+            }
+            Ok(data.end_byte_list()?)
+        }
+        _ => {
+            let items = items
+                .into_iter()
+                .filter_map(|item| match item {
+                    SliceItem::Item(addr) => Some(addr),
+                    _ => None,
+                })
+                .collect();
+            Ok(add_list(items, data)?)
+        }
+    }
+}
+
+/// Start and end of a slice's range, with a Unit start as zero and a Unit end as [`None`].
+type SliceBounds<Data> = (<Data as GarnishData>::Number, Option<<Data as GarnishData>::Number>);
+
+fn slice_range<Data: GarnishData>(range: Data::Size, data: &Data) -> Result<SliceBounds<Data>, UtilityError<Data::Error>> {
+    let (start, end) = match data.get_data_type(range.clone())? {
+        GarnishDataType::Range => data.get_range(range.clone())?,
+        t => Err(UtilityError::UnsupportedType(format!("slice range at {} is {:?}, expected Range", range, t)))?,
+    };
+
+    let start = range_bound(start, data)?.filter(|start| start > &Data::Number::zero()).unwrap_or_else(Data::Number::zero);
+    Ok((start, range_bound(end, data)?))
+}
+
+fn range_bound<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Option<Data::Number>, UtilityError<Data::Error>> {
+    match data.get_data_type(addr.clone())? {
+        GarnishDataType::Number => Ok(Some(data.get_number(addr)?)),
+        GarnishDataType::Unit => Ok(None),
+        t => Err(UtilityError::UnsupportedType(format!("range bound at {} is {:?}, expected Number or Unit", addr, t))),
+    }
+}
+
+fn offset<Data: GarnishData>(base: Data::Number, by: Data::Number) -> Result<Data::Number, UtilityError<Data::Error>> {
+    match base.clone().plus(by.clone()) {
+        Some(n) => Ok(n),
+        None => Err(UtilityError::InvalidRange(format!("{} can't be offset by {}", base, by))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleData, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::GarnishData;
+    use crate::testing::GarnishValue;
+    use crate::{assert_garnish_eq, read_bytes, read_string, resolve_slice, write_bytes, write_str, SliceItem, SliceIterator, UtilityError, ValueBuilder};
+
+    fn number(n: i32) -> GarnishValue<SimpleNumber, char, u8, u64, usize> {
+        GarnishValue::Number(SimpleNumber::Integer(n))
+    }
+
+    fn make_slice(data: &mut SimpleGarnishData, value: usize, start: Option<i32>, end: Option<i32>) -> usize {
+        ValueBuilder::new(data)
+            .slice(
+                |v| v.value(value),
+                |v| {
+                    v.range(
+                        |v| match start {
+                            Some(n) => v.number(n.into()),
+                            None => v.unit(),
+                        },
+                        |v| match end {
+                            Some(n) => v.number(n.into()),
+                            None => v.unit(),
+                        },
+                    )
+                },
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn slice_list() {
+        let mut data = SimpleGarnishData::new();
+        let list = ValueBuilder::new(&mut data).list(|l| l.items(0..5, |v, n| v.number(n.into()))).unwrap();
+        let slice = make_slice(&mut data, list, Some(1), Some(3));
+        let open = make_slice(&mut data, list, Some(3), None);
+
+        let resolved = resolve_slice(slice, &mut data).unwrap();
+        let resolved_open = resolve_slice(open, &mut data).unwrap();
+
+        assert_garnish_eq!(data, resolved, GarnishValue::List(vec![number(1), number(2), number(3)]));
+        assert_garnish_eq!(data, resolved_open, GarnishValue::List(vec![number(3), number(4)]));
+    }
+
+    #[test]
+    fn slice_char_and_byte_lists() {
+        let mut data = SimpleGarnishData::new();
+        let chars = write_str("garnish", &mut data).unwrap();
+        let bytes = write_bytes(&[1, 2, 3, 4], &mut data).unwrap();
+        let char_slice = make_slice(&mut data, chars, Some(1), Some(3));
+        let byte_slice = make_slice(&mut data, bytes, None, Some(1));
+
+        let resolved_chars = resolve_slice(char_slice, &mut data).unwrap();
+        let resolved_bytes = resolve_slice(byte_slice, &mut data).unwrap();
+
+        assert_eq!(read_string(resolved_chars, &data).unwrap(), "arn");
+        assert_eq!(read_bytes(resolved_bytes, &data).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn slice_concatenation_without_copying() {
+        let mut data = SimpleGarnishData::new();
+        let concat = ValueBuilder::new(&mut data)
+            .concatenation(|v| v.number(0.into()), |v| v.list(|l| l.items(1..4, |v, n| v.number(n.into()))))
+            .unwrap();
+        let slice = make_slice(&mut data, concat, Some(1), Some(2));
+        let len = data.get_data_len();
+
+        let items = SliceIterator::new(slice, &data).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+
+        assert_eq!(data.get_data_len(), len);
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0], SliceItem::Item(addr) if data.get_number(addr).unwrap() == SimpleNumber::Integer(1)));
+    }
+
+    #[test]
+    fn nested_slice() {
+        let mut data = SimpleGarnishData::new();
+        let list = ValueBuilder::new(&mut data).list(|l| l.items(0..10, |v, n| v.number(n.into()))).unwrap();
+        let inner = make_slice(&mut data, list, Some(2), Some(6));
+        let outer = make_slice(&mut data, inner, Some(1), Some(10));
+
+        let resolved = resolve_slice(outer, &mut data).unwrap();
+
+        assert_garnish_eq!(data, resolved, GarnishValue::List(vec![number(3), number(4), number(5), number(6)]));
+    }
+
+    #[test]
+    fn invalid_slices() {
+        let mut data = SimpleGarnishData::new();
+        let unit = data.add_unit().unwrap();
+        let unit_slice = make_slice(&mut data, unit, None, None);
+        let range = ValueBuilder::new(&mut data).range(|v| v.number(0.into()), |v| v.number(1.into())).unwrap();
+        let cyclic = data.get_data_len();
+        data.get_data_mut().push(SimpleData::Slice(cyclic, range));
+
+        assert!(matches!(SliceIterator::new(unit_slice, &data), Err(UtilityError::UnsupportedType(_))));
+        assert!(matches!(SliceIterator::new(cyclic, &data), Err(UtilityError::Cycle(_))));
+    }
+}
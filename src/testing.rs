@@ -0,0 +1,318 @@
+//! Assertions for checking values in any [`GarnishData`] implementation against a host-side description.
+//!
+//! Available with the `testing` feature.
+
+use std::fmt::{Arguments, Display};
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::data::{extend, join, read_byte_list, read_char_list, DataDiff, DiffKind, Difference, PathSegment};
+use crate::ConcatenationIterator;
+
+/// Host-side description of a value, compared against stored data with [`check_garnish_value`] or [`assert_garnish_eq!`](crate::assert_garnish_eq).
+#[derive(Debug, Clone, PartialEq)]
+pub enum GarnishValue<Number, Char, Byte, Symbol, Size> {
+    /// Matches any value.
+    Any,
+    Unit,
+    True,
+    False,
+    Number(Number),
+    Type(GarnishDataType),
+    Char(Char),
+    /// Compared against the [`std::fmt::Display`] output of each character.
+    CharList(String),
+    Byte(Byte),
+    ByteList(Vec<Byte>),
+    Symbol(Symbol),
+    Expression(Size),
+    External(Size),
+    Pair(Box<Self>, Box<Self>),
+    Range(Box<Self>, Box<Self>),
+    /// Items of the concatenation, regardless of how it is nested.
+    Concatenation(Vec<Self>),
+    Slice(Box<Self>, Box<Self>),
+    List(Vec<Self>),
+}
+
+pub type DataValue<Data> = GarnishValue<
+    <Data as GarnishData>::Number,
+    <Data as GarnishData>::Char,
+    <Data as GarnishData>::Byte,
+    <Data as GarnishData>::Symbol,
+    <Data as GarnishData>::Size,
+>;
+
+impl<Number, Char, Byte, Symbol, Size> GarnishValue<Number, Char, Byte, Symbol, Size> {
+    pub fn char_list(value: &str) -> Self {
+        GarnishValue::CharList(value.to_string())
+    }
+
+    pub fn pair(left: Self, right: Self) -> Self {
+        GarnishValue::Pair(Box::new(left), Box::new(right))
+    }
+
+    pub fn range(start: Self, end: Self) -> Self {
+        GarnishValue::Range(Box::new(start), Box::new(end))
+    }
+
+    pub fn slice(value: Self, range: Self) -> Self {
+        GarnishValue::Slice(Box::new(value), Box::new(range))
+    }
+
+    /// Type the described value must have, None for [`GarnishValue::Any`].
+    pub fn data_type(&self) -> Option<GarnishDataType> {
+        Some(match self {
+            GarnishValue::Any => return None,
+            GarnishValue::Unit => GarnishDataType::Unit,
+            GarnishValue::True => GarnishDataType::True,
+            GarnishValue::False => GarnishDataType::False,
+            GarnishValue::Number(_) => GarnishDataType::Number,
+            GarnishValue::Type(_) => GarnishDataType::Type,
+            GarnishValue::Char(_) => GarnishDataType::Char,
+            GarnishValue::CharList(_) => GarnishDataType::CharList,
+            GarnishValue::Byte(_) => GarnishDataType::Byte,
+            GarnishValue::ByteList(_) => GarnishDataType::ByteList,
+            GarnishValue::Symbol(_) => GarnishDataType::Symbol,
+            GarnishValue::Expression(_) => GarnishDataType::Expression,
+            GarnishValue::External(_) => GarnishDataType::External,
+            GarnishValue::Pair(_, _) => GarnishDataType::Pair,
+            GarnishValue::Range(_, _) => GarnishDataType::Range,
+            GarnishValue::Concatenation(_) => GarnishDataType::Concatenation,
+            GarnishValue::Slice(_, _) => GarnishDataType::Slice,
+            GarnishValue::List(_) => GarnishDataType::List,
+        })
+    }
+}
+
+/// Compares the value at the given address against the expected description.
+///
+/// Differences have the stored value on the left and the expected value on the right.
+pub fn check_garnish_value<Data: GarnishData>(
+    data: &Data,
+    addr: Data::Size,
+    expected: &DataValue<Data>,
+) -> Result<DataDiff, Data::Error> {
+    let mut diff = DataDiff::default();
+    check_internal(data, addr, expected, vec![], &mut diff)?;
+    Ok(diff)
+}
+
+/// Panics with the differences and expected value if the value at the given address doesn't match.
+/// Used by [`assert_garnish_eq!`](crate::assert_garnish_eq).
+#[track_caller]
+pub fn assert_garnish_value<Data: GarnishData>(
+    data: &Data,
+    addr: Data::Size,
+    expected: &DataValue<Data>,
+    message: Option<Arguments>,
+) {
+    let diff = match check_garnish_value(data, addr.clone(), expected) {
+        Ok(diff) => diff,
+        Err(e) => panic!("failed to read garnish value at {}: {}", addr, e),
+    };
+
+    if !diff.is_empty() {
+        let message = message.map(|m| format!(": {}", m)).unwrap_or_default();
+        panic!(
+            "garnish value at {} doesn't match expected{}\ndifferences (actual != expected):\n{}expected: {:#?}",
+            addr, message, diff, expected
+        );
+    }
+}
+
+/// Asserts the value at an address of a [`GarnishData`] implementation matches a [`GarnishValue`].
+///
+/// ```ignore
+/// assert_garnish_eq!(data, addr, GarnishValue::pair(GarnishValue::Symbol(1), GarnishValue::char_list("abc")));
+/// ```
+#[macro_export]
+macro_rules! assert_garnish_eq {
+    ($data:expr, $addr:expr, $expected:expr $(,)?) => {
+        $crate::testing::assert_garnish_value(&$data, $addr, &$expected, None)
+    };
+    ($data:expr, $addr:expr, $expected:expr, $($arg:tt)+) => {
+        $crate::testing::assert_garnish_value(&$data, $addr, &$expected, Some(format_args!($($arg)+)))
+    };
+}
+
+fn check_internal<Data: GarnishData>(
+    data: &Data,
+    addr: Data::Size,
+    expected: &DataValue<Data>,
+    path: Vec<PathSegment>,
+    diff: &mut DataDiff,
+) -> Result<(), Data::Error> {
+    let expected_type = match expected.data_type() {
+        Some(t) => t,
+        None => return Ok(()),
+    };
+
+    let actual_type = data.get_data_type(addr.clone())?;
+    if actual_type != expected_type {
+        diff.differences.push(Difference { path, kind: DiffKind::TypeMismatch(actual_type, expected_type) });
+        return Ok(());
+    }
+
+    let mismatch = match expected {
+        GarnishValue::Any | GarnishValue::Unit | GarnishValue::True | GarnishValue::False => None,
+        GarnishValue::Number(v) => compare(&data.get_number(addr)?, v),
+        GarnishValue::Type(v) => {
+            let actual = data.get_type(addr)?;
+            (actual != *v).then(|| (format!("{:?}", actual), format!("{:?}", v)))
+        }
+        GarnishValue::Char(v) => compare(&data.get_char(addr)?, v),
+        GarnishValue::Byte(v) => compare(&data.get_byte(addr)?, v),
+        GarnishValue::Symbol(v) => compare(&data.get_symbol(addr)?, v),
+        GarnishValue::Expression(v) => compare(&data.get_expression(addr)?, v),
+        GarnishValue::External(v) => compare(&data.get_external(addr)?, v),
+        GarnishValue::CharList(v) => {
+            let actual = join(&read_char_list(addr, data)?, "");
+            (actual != *v).then(|| (format!("\"{}\"", actual), format!("\"{}\"", v)))
+        }
+        GarnishValue::ByteList(v) => {
+            let actual = read_byte_list(addr, data)?;
+            (actual != *v).then(|| (format!("'{}'", join(&actual, " ")), format!("'{}'", join(v, " "))))
+        }
+        GarnishValue::Pair(left, right) => {
+            let (l, r) = data.get_pair(addr)?;
+            check_internal(data, l, left, extend(&path, PathSegment::Left), diff)?;
+            check_internal(data, r, right, extend(&path, PathSegment::Right), diff)?;
+            None
+        }
+        GarnishValue::Range(start, end) => {
+            let (s, e) = data.get_range(addr)?;
+            check_internal(data, s, start, extend(&path, PathSegment::Start), diff)?;
+            check_internal(data, e, end, extend(&path, PathSegment::End), diff)?;
+            None
+        }
+        GarnishValue::Slice(value, range) => {
+            let (v, r) = data.get_slice(addr)?;
+            check_internal(data, v, value, extend(&path, PathSegment::SliceValue), diff)?;
+            check_internal(data, r, range, extend(&path, PathSegment::SliceRange), diff)?;
+            None
+        }
+        GarnishValue::Concatenation(items) => {
+            let actual = ConcatenationIterator::new(addr, data).collect::<Result<Vec<Data::Size>, Data::Error>>()?;
+            check_items(data, actual, items, &path, diff)?;
+            None
+        }
+        GarnishValue::List(items) => {
+            let mut actual = vec![];
+            for i in data.get_list_items_iter(addr.clone()) {
+                actual.push(data.get_list_item(addr.clone(), i)?);
+            }
+            check_items(data, actual, items, &path, diff)?;
+            None
+        }
+    };
+
+    if let Some((actual, expected)) = mismatch {
+        diff.differences.push(Difference { path, kind: DiffKind::ValueMismatch(actual, expected) });
+    }
+
+    Ok(())
+}
+
+fn check_items<Data: GarnishData>(
+    data: &Data,
+    actual: Vec<Data::Size>,
+    expected: &[DataValue<Data>],
+    path: &[PathSegment],
+    diff: &mut DataDiff,
+) -> Result<(), Data::Error> {
+    if actual.len() != expected.len() {
+        diff.differences.push(Difference { path: path.to_vec(), kind: DiffKind::LengthMismatch(actual.len(), expected.len()) });
+    }
+
+    for (i, (addr, expected)) in actual.into_iter().zip(expected.iter()).enumerate() {
+        check_internal(data, addr, expected, extend(path, PathSegment::Index(i)), diff)?;
+    }
+
+    Ok(())
+}
+
+fn compare<T: PartialEq + Display>(actual: &T, expected: &T) -> Option<(String, String)> {
+    (actual != expected).then(|| (actual.to_string(), expected.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::{GarnishData, GarnishDataType};
+    use crate::testing::{check_garnish_value, GarnishValue};
+
+    fn make_list(data: &mut SimpleGarnishData) -> usize {
+        let d1 = data.add_symbol(1).unwrap();
+        let d2 = data.parse_add_char_list("\"abc\"").unwrap();
+        let d3 = data.add_pair((d1, d2)).unwrap();
+        let d4 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+
+        data.start_list(2).unwrap();
+        data.add_to_list(d3, true).unwrap();
+        data.add_to_list(d4, false).unwrap();
+        data.end_list().unwrap()
+    }
+
+    #[test]
+    fn matching_value() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data);
+
+        assert_garnish_eq!(
+            data,
+            list,
+            GarnishValue::List(vec![
+                GarnishValue::pair(GarnishValue::Symbol(1), GarnishValue::char_list("abc")),
+                GarnishValue::Number(SimpleNumber::Integer(10)),
+            ])
+        );
+    }
+
+    #[test]
+    fn any_matches() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data);
+
+        assert_garnish_eq!(data, list, GarnishValue::List(vec![GarnishValue::Any, GarnishValue::Any]), "list {}", list);
+    }
+
+    #[test]
+    fn differences() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data);
+
+        let diff = check_garnish_value(
+            &data,
+            list,
+            &GarnishValue::List(vec![
+                GarnishValue::pair(GarnishValue::Symbol(1), GarnishValue::char_list("abd")),
+                GarnishValue::Number(SimpleNumber::Integer(20)),
+                GarnishValue::Unit,
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            diff.to_string(),
+            "$: length 2 != 3\n$[0].right: \"abc\" != \"abd\"\n$[1]: 10 != 20\n"
+        );
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let mut data = SimpleGarnishData::new();
+        let addr = data.add_number(SimpleNumber::Integer(10)).unwrap();
+
+        let diff = check_garnish_value(&data, addr, &GarnishValue::Symbol(10)).unwrap();
+
+        assert_eq!(diff.to_string(), format!("$: type {:?} != {:?}\n", GarnishDataType::Number, GarnishDataType::Symbol));
+    }
+
+    #[test]
+    #[should_panic(expected = "differences (actual != expected):\n$: 10 != 20\n")]
+    fn assert_panics() {
+        let mut data = SimpleGarnishData::new();
+        let addr = data.add_number(SimpleNumber::Integer(10)).unwrap();
+
+        assert_garnish_eq!(data, addr, GarnishValue::Number(SimpleNumber::Integer(20)));
+    }
+}
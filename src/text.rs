@@ -0,0 +1,366 @@
+use std::char::REPLACEMENT_CHARACTER;
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::number::number_from_u64;
+use crate::UtilityError;
+
+/// Reads the CharList at the given address into a [`String`].
+///
+/// Each character is converted with [`GarnishData::char_to_number`] and must be a Unicode scalar value.
+pub fn read_string<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<String, UtilityError<Data::Error>> {
+    read_string_internal(addr, data, ConversionMode::Strict)
+}
+
+fn read_string_internal<Data: GarnishData>(
+    addr: Data::Size,
+    data: &Data,
+    mode: ConversionMode,
+) -> Result<String, UtilityError<Data::Error>> {
+    expect_type(addr.clone(), GarnishDataType::CharList, data)?;
+
+    let mut value = String::new();
+    for (index, i) in data.get_char_list_iter(addr.clone()).enumerate() {
+        let c = data.get_char_list_item(addr.clone(), i)?;
+        let code = Data::char_to_number(c.clone()).and_then(|n| n.to_string().parse::<u32>().ok());
+
+        match (code.and_then(char::from_u32), mode) {
+            (Some(c), _) => value.push(c),
+            (None, ConversionMode::Lossy) => value.push(REPLACEMENT_CHARACTER),
+            (None, ConversionMode::Strict) => Err(UtilityError::InvalidChar(format!(
+                "character {:?} at index {} of char list at {} has no Unicode scalar value",
+                c.to_string(),
+                index,
+                addr
+            )))?,
+        }
+    }
+
+    Ok(value)
+}
+
+/// Reads the ByteList at the given address into a [`Vec<u8>`].
+///
+/// Each byte is converted with [`GarnishData::byte_to_number`] and must be between 0 and 255.
+pub fn read_bytes<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Vec<u8>, UtilityError<Data::Error>> {
+    expect_type(addr.clone(), GarnishDataType::ByteList, data)?;
+
+    let mut value = vec![];
+    for (index, i) in data.get_byte_list_iter(addr.clone()).enumerate() {
+        let b = data.get_byte_list_item(addr.clone(), i)?;
+
+        match Data::byte_to_number(b.clone()).and_then(|n| n.to_string().parse::<u8>().ok()) {
+            Some(b) => value.push(b),
+            None => Err(UtilityError::InvalidByte(format!(
+                "byte {} at index {} of byte list at {} isn't between 0 and 255",
+                b, index, addr
+            )))?,
+        }
+    }
+
+    Ok(value)
+}
+
+/// Adds a CharList of the characters in `value`.
+///
+/// Each character is converted with [`GarnishData::number_to_char`], failing with [`UtilityError::InvalidChar`]
+/// before the list is started if the implementation can't store it.
+pub fn write_str<Data: GarnishData>(value: &str, data: &mut Data) -> Result<Data::Size, UtilityError<Data::Error>> {
+    write_str_internal(value, data, ConversionMode::Strict)
+}
+
+fn write_str_internal<Data: GarnishData>(
+    value: &str,
+    data: &mut Data,
+    mode: ConversionMode,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let mut chars = vec![];
+    for (index, c) in value.chars().enumerate() {
+        let converted = match (convert_char::<Data>(c)?, mode) {
+            (Some(converted), _) => Some(converted),
+            (None, ConversionMode::Lossy) => match convert_char::<Data>(REPLACEMENT_CHARACTER)? {
+                Some(converted) => Some(converted),
+                None => convert_char::<Data>('?')?,
+            },
+            (None, ConversionMode::Strict) => None,
+        };
+
+        match converted {
+            Some(converted) => chars.push(converted),
+            None => Err(UtilityError::InvalidChar(format!(
+                "{:?} (U+{:04X}) at index {} can't be stored as a character",
+                c, c as u32, index
+            )))?,
+        }
+    }
+
+    data.start_char_list()?;
+    for c in chars {
+        data.add_to_char_list(c)?;
+    }
+
+    Ok(data.end_char_list()?)
+}
+
+/// Adds a ByteList of the bytes in `value`.
+///
+/// Each byte is converted with [`GarnishData::number_to_byte`], failing with [`UtilityError::InvalidByte`]
+/// before the list is started if the implementation can't store it.
+pub fn write_bytes<Data: GarnishData>(value: &[u8], data: &mut Data) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let mut bytes = vec![];
+    for (index, b) in value.iter().enumerate() {
+        let number = number_from_u64::<Data>(*b as u64)?;
+        let converted = Data::number_to_byte(number.clone())
+            .filter(|converted| Data::byte_to_number(converted.clone()).as_ref() == Some(&number));
+
+        match converted {
+            Some(converted) => bytes.push(converted),
+            None => Err(UtilityError::InvalidByte(format!("{} at index {} can't be stored as a byte", b, index)))?,
+        }
+    }
+
+    data.start_byte_list()?;
+    for b in bytes {
+        data.add_to_byte_list(b)?;
+    }
+
+    Ok(data.end_byte_list()?)
+}
+
+/// Encoding used to convert between a ByteList and a CharList.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// Each byte is the character with the same code point, so only characters up to U+00FF can be encoded.
+    Latin1,
+}
+
+/// How text conversions handle bytes or characters that can't be converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionMode {
+    /// Fail with [`UtilityError::InvalidByte`] or [`UtilityError::InvalidChar`].
+    Strict,
+    /// Replace with U+FFFD, or `?` when the replacement character can't be stored or encoded.
+    Lossy,
+}
+
+/// Adds a CharList of the text in the ByteList at the given address.
+///
+/// Bytes are read as [`u8`] and decoded into a Rust [`String`] before characters are converted as in [`write_str`].
+/// [`GarnishData::byte_to_char`] maps one byte to one character, which can't decode UTF-8 or UTF-16 sequences.
+pub fn decode_byte_list<Data: GarnishData>(
+    addr: Data::Size,
+    data: &mut Data,
+    encoding: TextEncoding,
+    mode: ConversionMode,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let bytes = read_bytes(addr.clone(), data)?;
+
+    let text = match (encoding, mode) {
+        (TextEncoding::Utf8, ConversionMode::Lossy) => String::from_utf8_lossy(&bytes).into_owned(),
+        (TextEncoding::Utf8, ConversionMode::Strict) => match String::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => Err(UtilityError::InvalidByte(format!(
+                "invalid UTF-8 at index {} of byte list at {}",
+                e.utf8_error().valid_up_to(),
+                addr
+            )))?,
+        },
+        (TextEncoding::Utf16Le, _) | (TextEncoding::Utf16Be, _) => {
+            let mut units = vec![];
+            for pair in bytes.chunks(2) {
+                match (pair, encoding) {
+                    ([low, high], TextEncoding::Utf16Le) => units.push(u16::from_le_bytes([*low, *high])),
+                    ([high, low], _) => units.push(u16::from_be_bytes([*high, *low])),
+                    _ if mode == ConversionMode::Lossy => units.push(REPLACEMENT_CHARACTER as u16),
+                    _ => Err(UtilityError::InvalidByte(format!("byte list at {} has an odd number of bytes for UTF-16", addr)))?,
+                }
+            }
+
+            let mut text = String::new();
+            // byte offset of the current code unit, surrogate pairs take two
+            let mut offset = 0;
+            for c in char::decode_utf16(units) {
+                match (c, mode) {
+                    (Ok(c), _) => {
+                        text.push(c);
+                        offset += c.len_utf16() * 2;
+                    }
+                    (Err(_), ConversionMode::Lossy) => {
+                        text.push(REPLACEMENT_CHARACTER);
+                        offset += 2;
+                    }
+                    (Err(e), ConversionMode::Strict) => Err(UtilityError::InvalidByte(format!(
+                        "unpaired surrogate {:04X} at index {} of byte list at {}",
+                        e.unpaired_surrogate(),
+                        offset,
+                        addr
+                    )))?,
+                }
+            }
+
+            text
+        }
+        (TextEncoding::Latin1, _) => bytes.into_iter().map(char::from).collect(),
+    };
+
+    write_str_internal(&text, data, mode)
+}
+
+/// Adds a ByteList of the text in the CharList at the given address.
+pub fn encode_char_list<Data: GarnishData>(
+    addr: Data::Size,
+    data: &mut Data,
+    encoding: TextEncoding,
+    mode: ConversionMode,
+) -> Result<Data::Size, UtilityError<Data::Error>> {
+    let text = read_string_internal(addr.clone(), data, mode)?;
+
+    let bytes = match encoding {
+        TextEncoding::Utf8 => text.into_bytes(),
+        TextEncoding::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        TextEncoding::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+        TextEncoding::Latin1 => {
+            let mut bytes = vec![];
+            for (index, c) in text.chars().enumerate() {
+                match (u8::try_from(c), mode) {
+                    (Ok(b), _) => bytes.push(b),
+                    (Err(_), ConversionMode::Lossy) => bytes.push(b'?'),
+                    (Err(_), ConversionMode::Strict) => Err(UtilityError::InvalidChar(format!(
+                        "{:?} at index {} of char list at {} can't be encoded as Latin-1",
+                        c, index, addr
+                    )))?,
+                }
+            }
+            bytes
+        }
+    };
+
+    write_bytes(&bytes, data)
+}
+
+/// Converts a character with [`GarnishData::number_to_char`], checking it converts back to the same number.
+fn convert_char<Data: GarnishData>(c: char) -> Result<Option<Data::Char>, Data::Error> {
+    let number = number_from_u64::<Data>(c as u64)?;
+    Ok(Data::number_to_char(number.clone())
+        .filter(|converted| Data::char_to_number(converted.clone()).as_ref() == Some(&number)))
+}
+
+fn expect_type<Data: GarnishData>(addr: Data::Size, expected: GarnishDataType, data: &Data) -> Result<(), UtilityError<Data::Error>> {
+    match data.get_data_type(addr.clone())? {
+        t if t == expected => Ok(()),
+        t => Err(UtilityError::UnsupportedType(format!("value at {} is {:?}, expected {:?}", addr, t, expected))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{DataError, SimpleGarnishData};
+    use garnish_lang_traits::GarnishData;
+    use crate::{decode_byte_list, encode_char_list, read_bytes, read_string, write_bytes, write_str, ConversionMode, TextEncoding, UtilityError};
+
+    #[test]
+    fn string_round_trip() {
+        let mut data = SimpleGarnishData::new();
+
+        let addr = write_str("some stuff", &mut data).unwrap();
+
+        assert_eq!(read_string(addr, &data).unwrap(), "some stuff");
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut data = SimpleGarnishData::new();
+
+        let addr = write_bytes(&[0, 100, 255], &mut data).unwrap();
+
+        assert_eq!(read_bytes(addr, &data).unwrap(), vec![0, 100, 255]);
+    }
+
+    #[test]
+    fn invalid_char() {
+        let mut data = SimpleGarnishData::new();
+        let len = data.get_data_len();
+
+        let result = write_str("a€", &mut data);
+
+        match result {
+            Err(UtilityError::InvalidChar(message)) => assert_eq!(message, "'€' (U+20AC) at index 1 can't be stored as a character"),
+            _ => panic!("expected invalid char error, got {:?}", result),
+        }
+        assert_eq!(data.get_data_len(), len);
+    }
+
+    #[test]
+    fn read_wrong_type() {
+        let mut data = SimpleGarnishData::new();
+        let addr = data.add_unit().unwrap();
+
+        assert!(matches!(read_string(addr, &data), Err(UtilityError::UnsupportedType(_))));
+        assert!(matches!(read_bytes(addr, &data), Err(UtilityError::UnsupportedType(_))));
+    }
+
+    #[test]
+    fn utf8() {
+        let mut data = SimpleGarnishData::new();
+        let text = write_str("hello", &mut data).unwrap();
+
+        let encoded = encode_char_list(text, &mut data, TextEncoding::Utf8, ConversionMode::Strict).unwrap();
+        let bytes = read_bytes(encoded, &data).unwrap();
+        let invalid = write_bytes(&[104, 0xFF, 105], &mut data).unwrap();
+
+        assert_eq!(bytes, b"hello");
+        assert!(matches!(
+            decode_byte_list(invalid, &mut data, TextEncoding::Utf8, ConversionMode::Strict),
+            Err(UtilityError::InvalidByte(_))
+        ));
+
+        let lossy = decode_byte_list(invalid, &mut data, TextEncoding::Utf8, ConversionMode::Lossy).unwrap();
+        assert_eq!(read_string(lossy, &data).unwrap(), "h?i");
+    }
+
+    #[test]
+    fn utf16() {
+        let mut data = SimpleGarnishData::new();
+        let le = write_bytes(&[104, 0, 105, 0], &mut data).unwrap();
+        let be = write_bytes(&[0, 104, 0, 105], &mut data).unwrap();
+        let odd = write_bytes(&[0, 104, 0], &mut data).unwrap();
+
+        let from_le = decode_byte_list(le, &mut data, TextEncoding::Utf16Le, ConversionMode::Strict).unwrap();
+        let from_be = decode_byte_list(be, &mut data, TextEncoding::Utf16Be, ConversionMode::Strict).unwrap();
+        let encoded = encode_char_list(from_le, &mut data, TextEncoding::Utf16Be, ConversionMode::Strict).unwrap();
+
+        assert_eq!(read_string(from_le, &data).unwrap(), "hi");
+        assert_eq!(read_string(from_be, &data).unwrap(), "hi");
+        assert_eq!(read_bytes(encoded, &data).unwrap(), vec![0, 104, 0, 105]);
+        assert!(matches!(
+            decode_byte_list(odd, &mut data, TextEncoding::Utf16Be, ConversionMode::Strict),
+            Err(UtilityError::InvalidByte(_))
+        ));
+    }
+
+    #[test]
+    fn utf16_error_offset_after_surrogate_pair() {
+        let mut data = SimpleGarnishData::new();
+        let bytes = write_bytes(&[0, 104, 0xD8, 0x3D, 0xDE, 0x00, 0xDC, 0x00], &mut data).unwrap();
+
+        let result = decode_byte_list(bytes, &mut data, TextEncoding::Utf16Be, ConversionMode::Strict);
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            UtilityError::<DataError>::InvalidByte(format!("unpaired surrogate DC00 at index 6 of byte list at {}", bytes)).to_string()
+        );
+    }
+
+    #[test]
+    fn latin1() {
+        let mut data = SimpleGarnishData::new();
+        let bytes = write_bytes(&[104, 105], &mut data).unwrap();
+
+        let text = decode_byte_list(bytes, &mut data, TextEncoding::Latin1, ConversionMode::Strict).unwrap();
+        let encoded = encode_char_list(text, &mut data, TextEncoding::Latin1, ConversionMode::Strict).unwrap();
+
+        assert_eq!(read_string(text, &data).unwrap(), "hi");
+        assert_eq!(read_bytes(encoded, &data).unwrap(), vec![104, 105]);
+    }
+}
@@ -0,0 +1,555 @@
+use garnish_lang_traits::{GarnishData, GarnishDataType};
+use crate::address_set::AddressSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitAction {
+    Continue,
+    /// Don't walk the children of the current value. Leave method is still called for compound values.
+    SkipChildren,
+    /// End the walk without visiting any more values.
+    Stop,
+}
+
+/// Callbacks for [`walk_data`]. Methods default to continuing the walk, except [`GarnishVisitor::visit_shared`] which skips values already walked.
+///
+/// Compound values (Pair, Range, List, Concatenation and Slice) receive an enter call before their children are walked
+/// and a leave call after.
+#[allow(unused_variables)]
+pub trait GarnishVisitor<Data: GarnishData> {
+    fn visit_invalid(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_custom(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_unit(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_true(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_false(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_number(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_type(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_char(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_char_list(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_byte(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_byte_list(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_symbol(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_expression(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn visit_external(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn enter_pair(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn leave_pair(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn enter_range(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn leave_range(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn enter_list(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn leave_list(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn enter_concatenation(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn leave_concatenation(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn enter_slice(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    fn leave_slice(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    /// Called instead of the type's visit method when a value that has already been walked is reached again.
+    /// Returning [`VisitAction::Continue`] walks the value again, any other action skips it.
+    fn visit_shared(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::SkipChildren)
+    }
+
+    /// Called when a value is reached from one of its own children. The value is never walked again.
+    fn visit_cycle(&mut self, addr: Data::Size, data: &Data) -> Result<VisitAction, Data::Error> {
+        Ok(VisitAction::Continue)
+    }
+}
+
+/// Result of entering a node during [`traverse`].
+pub(crate) enum Step<Node, Context, Leave> {
+    /// Node has no children to walk and is not left.
+    Leaf,
+    /// Walk the given children in order, then leave the node with the given value.
+    Children(Vec<(Node, Context)>, Leave),
+    /// End the traversal without entering any more nodes.
+    Stop,
+}
+
+/// Step type for a given [`Traversal`].
+type TraversalStep<T> = Step<<T as Traversal>::Node, <T as Traversal>::Context, <T as Traversal>::Leave>;
+
+/// Callbacks for [`traverse`], the engine shared by the utilities that walk graphs of values.
+///
+/// Nodes are compared by `Node` to detect shared and cyclic references, `Context` travels with each node from its parent
+/// and `Leave` is handed back when a node is left after its children.
+#[allow(unused_variables)]
+pub(crate) trait Traversal {
+    type Node: PartialOrd + Clone;
+    type Context;
+    type Leave;
+    type Error;
+
+    fn enter(&mut self, node: Self::Node, context: Self::Context) -> Result<TraversalStep<Self>, Self::Error>;
+
+    fn leave(&mut self, node: Self::Node, leave: Self::Leave) -> Result<VisitAction, Self::Error> {
+        Ok(VisitAction::Continue)
+    }
+
+    /// Called when a node that has already been entered is reached again.
+    /// Returning [`VisitAction::Continue`] enters it again, any other action skips it.
+    fn shared(&mut self, node: &Self::Node, context: &Self::Context) -> Result<VisitAction, Self::Error> {
+        Ok(VisitAction::SkipChildren)
+    }
+
+    /// Called when a node is reached from one of its own children. The node is never entered again.
+    fn cycle(&mut self, node: &Self::Node, context: &Self::Context) -> Result<VisitAction, Self::Error> {
+        Ok(VisitAction::Continue)
+    }
+}
+
+enum Frame<Node, Context, Leave> {
+    Enter(Node, Context),
+    Leave(Node, Leave),
+}
+
+/// Depth first traversal from each root in turn, using an explicit stack so deeply nested values will not overflow the call stack.
+///
+/// Nodes already entered from an earlier root are treated as shared.
+pub(crate) fn traverse<T: Traversal>(
+    roots: impl IntoIterator<Item = (T::Node, T::Context)>,
+    traversal: &mut T,
+) -> Result<(), T::Error> {
+    let mut visited = AddressSet::new();
+    let mut path = AddressSet::new();
+
+    for (root, context) in roots {
+        let mut stack = vec![Frame::Enter(root, context)];
+
+        while let Some(frame) = stack.pop() {
+            let action = match frame {
+                Frame::Leave(node, leave) => {
+                    path.remove(&node);
+                    traversal.leave(node, leave)?
+                }
+                Frame::Enter(node, context) if path.contains(&node) => traversal.cycle(&node, &context)?,
+                Frame::Enter(node, context) => {
+                    let shared_action = match visited.insert(node.clone()) {
+                        true => VisitAction::Continue,
+                        false => traversal.shared(&node, &context)?,
+                    };
+
+                    match shared_action {
+                        VisitAction::Continue => match traversal.enter(node.clone(), context)? {
+                            Step::Leaf => VisitAction::Continue,
+                            Step::Stop => VisitAction::Stop,
+                            Step::Children(children, leave) => {
+                                path.insert(node.clone());
+                                stack.push(Frame::Leave(node, leave));
+                                for (child, context) in children.into_iter().rev() {
+                                    stack.push(Frame::Enter(child, context));
+                                }
+
+                                VisitAction::Continue
+                            }
+                        },
+                        action => action,
+                    }
+                }
+            };
+
+            if action == VisitAction::Stop {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Adapts a [`GarnishVisitor`] to [`traverse`].
+struct VisitorTraversal<'a, Data, Visitor> {
+    data: &'a Data,
+    visitor: &'a mut Visitor,
+}
+
+impl<'a, Data: GarnishData, Visitor: GarnishVisitor<Data>> Traversal for VisitorTraversal<'a, Data, Visitor> {
+    type Node = Data::Size;
+    type Context = ();
+    type Leave = GarnishDataType;
+    type Error = Data::Error;
+
+    fn enter(&mut self, addr: Data::Size, _context: ()) -> Result<TraversalStep<Self>, Data::Error> {
+        let data_type = self.data.get_data_type(addr.clone())?;
+        let action = visit_value(addr.clone(), data_type, self.data, self.visitor)?;
+
+        Ok(match (action, is_compound(data_type)) {
+            (VisitAction::Stop, _) => Step::Stop,
+            (_, false) => Step::Leaf,
+            (VisitAction::SkipChildren, true) => Step::Children(vec![], data_type),
+            (VisitAction::Continue, true) => {
+                let children = children(addr, data_type, self.data)?.into_iter().map(|child| (child, ())).collect();
+                Step::Children(children, data_type)
+            }
+        })
+    }
+
+    fn leave(&mut self, addr: Data::Size, data_type: GarnishDataType) -> Result<VisitAction, Data::Error> {
+        match data_type {
+            GarnishDataType::Pair => self.visitor.leave_pair(addr, self.data),
+            GarnishDataType::Range => self.visitor.leave_range(addr, self.data),
+            GarnishDataType::List => self.visitor.leave_list(addr, self.data),
+            GarnishDataType::Concatenation => self.visitor.leave_concatenation(addr, self.data),
+            GarnishDataType::Slice => self.visitor.leave_slice(addr, self.data),
+            _ => Ok(VisitAction::Continue),
+        }
+    }
+
+    fn shared(&mut self, addr: &Data::Size, _context: &()) -> Result<VisitAction, Data::Error> {
+        self.visitor.visit_shared(addr.clone(), self.data)
+    }
+
+    fn cycle(&mut self, addr: &Data::Size, _context: &()) -> Result<VisitAction, Data::Error> {
+        self.visitor.visit_cycle(addr.clone(), self.data)
+    }
+}
+
+/// Depth first walk of the value at the given address, calling the matching visitor method for each value reached.
+///
+/// Children are walked in order using an explicit stack, so deeply nested values will not overflow the call stack.
+pub fn walk_data<Data: GarnishData, Visitor: GarnishVisitor<Data>>(
+    addr: Data::Size,
+    data: &Data,
+    visitor: &mut Visitor,
+) -> Result<(), Data::Error> {
+    traverse([(addr, ())], &mut VisitorTraversal { data, visitor })
+}
+
+/// Whether values of the given type refer to other values.
+pub(crate) fn is_compound(data_type: GarnishDataType) -> bool {
+    matches!(
+        data_type,
+        GarnishDataType::Pair
+            | GarnishDataType::Range
+            | GarnishDataType::List
+            | GarnishDataType::Concatenation
+            | GarnishDataType::Slice
+    )
+}
+
+fn visit_value<Data: GarnishData, Visitor: GarnishVisitor<Data>>(
+    addr: Data::Size,
+    data_type: GarnishDataType,
+    data: &Data,
+    visitor: &mut Visitor,
+) -> Result<VisitAction, Data::Error> {
+    match data_type {
+        GarnishDataType::Invalid => visitor.visit_invalid(addr, data),
+        GarnishDataType::Custom => visitor.visit_custom(addr, data),
+        GarnishDataType::Unit => visitor.visit_unit(addr, data),
+        GarnishDataType::True => visitor.visit_true(addr, data),
+        GarnishDataType::False => visitor.visit_false(addr, data),
+        GarnishDataType::Number => visitor.visit_number(addr, data),
+        GarnishDataType::Type => visitor.visit_type(addr, data),
+        GarnishDataType::Char => visitor.visit_char(addr, data),
+        GarnishDataType::CharList => visitor.visit_char_list(addr, data),
+        GarnishDataType::Byte => visitor.visit_byte(addr, data),
+        GarnishDataType::ByteList => visitor.visit_byte_list(addr, data),
+        GarnishDataType::Symbol => visitor.visit_symbol(addr, data),
+        GarnishDataType::Expression => visitor.visit_expression(addr, data),
+        GarnishDataType::External => visitor.visit_external(addr, data),
+        GarnishDataType::Pair => visitor.enter_pair(addr, data),
+        GarnishDataType::Range => visitor.enter_range(addr, data),
+        GarnishDataType::List => visitor.enter_list(addr, data),
+        GarnishDataType::Concatenation => visitor.enter_concatenation(addr, data),
+        GarnishDataType::Slice => visitor.enter_slice(addr, data),
+    }
+}
+
+/// Addresses a value refers to, in the order they're walked.
+pub(crate) fn children<Data: GarnishData>(
+    addr: Data::Size,
+    data_type: GarnishDataType,
+    data: &Data,
+) -> Result<Vec<Data::Size>, Data::Error> {
+    let (left, right) = match data_type {
+        GarnishDataType::Pair => data.get_pair(addr)?,
+        GarnishDataType::Range => data.get_range(addr)?,
+        GarnishDataType::Concatenation => data.get_concatenation(addr)?,
+        GarnishDataType::Slice => data.get_slice(addr)?,
+        GarnishDataType::List => {
+            let mut items = vec![];
+            for i in data.get_list_items_iter(addr.clone()) {
+                items.push(data.get_list_item(addr.clone(), i)?);
+            }
+
+            return Ok(items);
+        }
+        _ => return Ok(vec![]),
+    };
+
+    Ok(vec![left, right])
+}
+
+struct CycleVisitor<Size> {
+    cycle: Option<Size>,
+}
+
+impl<Data: GarnishData> GarnishVisitor<Data> for CycleVisitor<Data::Size> {
+    fn visit_cycle(&mut self, addr: Data::Size, _data: &Data) -> Result<VisitAction, Data::Error> {
+        self.cycle = Some(addr);
+        Ok(VisitAction::Stop)
+    }
+}
+
+/// Finds a value reachable from the given address that is reachable from one of its own children, if any.
+///
+/// Each value is only walked once, so this is safe to call on untrusted data before using utilities that expect acyclic values.
+pub fn find_cycle<Data: GarnishData>(addr: Data::Size, data: &Data) -> Result<Option<Data::Size>, Data::Error> {
+    let mut visitor = CycleVisitor { cycle: None };
+    walk_data(addr, data, &mut visitor)?;
+    Ok(visitor.cycle)
+}
+
+#[cfg(test)]
+mod tests {
+    use garnish_lang_simple_data::{DataError, SimpleData, SimpleGarnishData, SimpleNumber};
+    use garnish_lang_traits::GarnishData;
+    use crate::{find_cycle, walk_data, GarnishVisitor, VisitAction};
+
+    #[derive(Default)]
+    struct EventVisitor {
+        events: Vec<String>,
+        skip: Option<usize>,
+        stop: Option<usize>,
+        walk_shared: bool,
+    }
+
+    impl EventVisitor {
+        fn action(&self, addr: usize) -> VisitAction {
+            if self.skip == Some(addr) {
+                VisitAction::SkipChildren
+            } else if self.stop == Some(addr) {
+                VisitAction::Stop
+            } else {
+                VisitAction::Continue
+            }
+        }
+    }
+
+    impl GarnishVisitor<SimpleGarnishData> for EventVisitor {
+        fn visit_number(&mut self, addr: usize, data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push(format!("{}", data.get_number(addr)?));
+            Ok(self.action(addr))
+        }
+
+        fn visit_symbol(&mut self, addr: usize, data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push(format!(":{}", data.get_symbol(addr)?));
+            Ok(self.action(addr))
+        }
+
+        fn enter_pair(&mut self, addr: usize, _data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push("pair(".to_string());
+            Ok(self.action(addr))
+        }
+
+        fn leave_pair(&mut self, _addr: usize, _data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push(")".to_string());
+            Ok(VisitAction::Continue)
+        }
+
+        fn enter_list(&mut self, addr: usize, _data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push("list(".to_string());
+            Ok(self.action(addr))
+        }
+
+        fn leave_list(&mut self, _addr: usize, _data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push(")".to_string());
+            Ok(VisitAction::Continue)
+        }
+
+        fn enter_concatenation(&mut self, addr: usize, _data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push("concat(".to_string());
+            Ok(self.action(addr))
+        }
+
+        fn leave_concatenation(&mut self, _addr: usize, _data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push(")".to_string());
+            Ok(VisitAction::Continue)
+        }
+
+        fn visit_shared(&mut self, addr: usize, _data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push(format!("shared {}", addr));
+            Ok(if self.walk_shared { VisitAction::Continue } else { VisitAction::SkipChildren })
+        }
+
+        fn visit_cycle(&mut self, addr: usize, _data: &SimpleGarnishData) -> Result<VisitAction, DataError> {
+            self.events.push(format!("cycle {}", addr));
+            Ok(VisitAction::Continue)
+        }
+    }
+
+    fn make_list(data: &mut SimpleGarnishData) -> usize {
+        let d1 = data.add_symbol(1).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let pair = data.add_pair((d1, d2)).unwrap();
+        let d3 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+
+        data.start_list(2).unwrap();
+        data.add_to_list(pair, true).unwrap();
+        data.add_to_list(d3, false).unwrap();
+        data.end_list().unwrap()
+    }
+
+    #[test]
+    fn walk_in_order() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data);
+
+        let mut visitor = EventVisitor::default();
+        walk_data(list, &data, &mut visitor).unwrap();
+
+        assert_eq!(visitor.events, vec!["list(", "pair(", ":1", "10", ")", "20", ")"]);
+    }
+
+    #[test]
+    fn skip_children() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data);
+        let pair = data.get_list_item(list, SimpleNumber::Integer(0)).unwrap();
+
+        let mut visitor = EventVisitor { skip: Some(pair), ..Default::default() };
+        walk_data(list, &data, &mut visitor).unwrap();
+
+        assert_eq!(visitor.events, vec!["list(", "pair(", ")", "20", ")"]);
+    }
+
+    #[test]
+    fn stop() {
+        let mut data = SimpleGarnishData::new();
+        let list = make_list(&mut data);
+        let pair = data.get_list_item(list, SimpleNumber::Integer(0)).unwrap();
+
+        let mut visitor = EventVisitor { stop: Some(pair), ..Default::default() };
+        walk_data(list, &data, &mut visitor).unwrap();
+
+        assert_eq!(visitor.events, vec!["list(", "pair("]);
+    }
+
+    #[test]
+    fn shared_values() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let pair = data.add_pair((d1, d2)).unwrap();
+        let cat = data.add_concatenation(pair, pair).unwrap();
+
+        let mut visitor = EventVisitor::default();
+        walk_data(cat, &data, &mut visitor).unwrap();
+
+        assert_eq!(visitor.events, vec!["concat(", "pair(", "10", "20", ")", format!("shared {}", pair).as_str(), ")"]);
+    }
+
+    #[test]
+    fn shared_values_walked_again() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let d2 = data.add_number(SimpleNumber::Integer(20)).unwrap();
+        let pair = data.add_pair((d1, d2)).unwrap();
+        let cat = data.add_concatenation(pair, pair).unwrap();
+
+        let mut visitor = EventVisitor { walk_shared: true, ..Default::default() };
+        walk_data(cat, &data, &mut visitor).unwrap();
+
+        let shared = format!("shared {}", pair);
+        let shared_d1 = format!("shared {}", d1);
+        let shared_d2 = format!("shared {}", d2);
+        assert_eq!(
+            visitor.events,
+            vec!["concat(", "pair(", "10", "20", ")", shared.as_str(), "pair(", shared_d1.as_str(), "10", shared_d2.as_str(), "20", ")", ")"]
+        );
+    }
+
+    #[test]
+    fn cycle() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let cat = data.get_data_len();
+        data.get_data_mut().push(SimpleData::Concatenation(d1, cat));
+
+        let mut visitor = EventVisitor::default();
+        walk_data(cat, &data, &mut visitor).unwrap();
+
+        assert_eq!(visitor.events, vec!["concat(", "10", format!("cycle {}", cat).as_str(), ")"]);
+    }
+
+    #[test]
+    fn find_cycle_in_data() {
+        let mut data = SimpleGarnishData::new();
+        let d1 = data.add_number(SimpleNumber::Integer(10)).unwrap();
+        let cat = data.get_data_len();
+        data.get_data_mut().push(SimpleData::Concatenation(d1, cat));
+        let list = make_list(&mut data);
+
+        assert_eq!(find_cycle(cat, &data).unwrap(), Some(cat));
+        assert_eq!(find_cycle(list, &data).unwrap(), None);
+    }
+}